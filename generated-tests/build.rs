@@ -0,0 +1,206 @@
+use odata_parser_rs::{Edmx, Property, PropertyType};
+use odata_rust_generator::{
+    generate, generate_to_out_dir, write_if_changed, GeneratorOptions, TypeOverride,
+};
+use std::{path::Path, str::FromStr};
+
+const NORTHWIND: &str = "../tests/northwind.xml";
+const CALENDAR: &str = "calendar.xml";
+
+fn main() {
+    generate_to_out_dir(
+        NORTHWIND,
+        "plain.rs",
+        &GeneratorOptions {
+            module_prefix: Some("plain".to_string()),
+            emit_tests: true,
+            validate: true,
+            to_json: true,
+            ..Default::default()
+        },
+    );
+
+    generate_to_out_dir(
+        NORTHWIND,
+        "lenient.rs",
+        &GeneratorOptions {
+            module_prefix: Some("lenient".to_string()),
+            lenient_bools: true,
+            lenient_numbers: true,
+            ..Default::default()
+        },
+    );
+
+    generate_to_out_dir(
+        NORTHWIND,
+        "decimals.rs",
+        &GeneratorOptions {
+            module_prefix: Some("decimals".to_string()),
+            emit_tests: true,
+            decimal_type: Some("i128".to_string()),
+            decimal_scale: Some(2),
+            ..Default::default()
+        },
+    );
+
+    generate_to_out_dir(
+        NORTHWIND,
+        "patchable.rs",
+        &GeneratorOptions {
+            module_prefix: Some("patchable".to_string()),
+            emit_tests: true,
+            emit_allow: true,
+            triple_state_options: true,
+            ..Default::default()
+        },
+    );
+
+    generate_to_out_dir(
+        NORTHWIND,
+        "display.rs",
+        &GeneratorOptions {
+            module_prefix: Some("display".to_string()),
+            emit_tests: true,
+            display: true,
+            typed_ids: true,
+            ..Default::default()
+        },
+    );
+
+    generate_to_out_dir(
+        CALENDAR,
+        "events.rs",
+        &GeneratorOptions {
+            module_prefix: Some("events".to_string()),
+            emit_tests: true,
+            display: true,
+            ..Default::default()
+        },
+    );
+
+    generate_to_out_dir(
+        NORTHWIND,
+        "macros.rs",
+        &GeneratorOptions {
+            module_prefix: Some("macros".to_string()),
+            select_macros: true,
+            ..Default::default()
+        },
+    );
+
+    generate_to_out_dir(
+        NORTHWIND,
+        "projections.rs",
+        &GeneratorOptions {
+            module_prefix: Some("projections".to_string()),
+            field_case: Some("snake".to_string()),
+            projections: vec![
+                (
+                    "Customer".to_string(),
+                    vec!["CompanyName".to_string(), "ContactName".to_string()],
+                ),
+                (
+                    "NorthwindModel.Order".to_string(),
+                    vec![
+                        "OrderID".to_string(),
+                        "OrderDate".to_string(),
+                        "Freight".to_string(),
+                    ],
+                ),
+            ]
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        },
+    );
+
+    generate_to_out_dir(
+        NORTHWIND,
+        "overrides.rs",
+        &GeneratorOptions {
+            module_prefix: Some("overrides".to_string()),
+            key_structs: true,
+            type_overrides: vec![(
+                "NorthwindModel.Order.OrderID".to_string(),
+                TypeOverride {
+                    rust_type: "crate::OrderNumber".to_string(),
+                    serde_with: None,
+                },
+            )]
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        },
+    );
+
+    // The only fixture without a prefix module, so its items end up at the root of the crate.
+    generate_to_out_dir(
+        NORTHWIND,
+        "unprefixed.rs",
+        &GeneratorOptions {
+            emit_tests: true,
+            emit_allow: true,
+            field_case: Some("original".to_string()),
+            any_entity: true,
+            triple_state_options: true,
+            visibility: Some("pub(crate)".to_string()),
+            ..Default::default()
+        },
+    );
+
+    generate_with_facets(
+        NORTHWIND,
+        "validation.rs",
+        &GeneratorOptions {
+            module_prefix: Some("validation".to_string()),
+            emit_tests: true,
+            validate: true,
+            decimal_type: Some("i128".to_string()),
+            decimal_scale: Some(2),
+            ..Default::default()
+        },
+        |entity, property| match (entity, property.name.as_str(), &mut property.inner) {
+            ("Customer", "CustomerID", PropertyType::String { max_length, .. }) => {
+                *max_length = Some(5)
+            }
+            ("Customer", "ContactName", PropertyType::String { max_length, .. }) => {
+                *max_length = Some(30)
+            }
+            ("Category", "Picture", PropertyType::Binary { max_length, .. }) => {
+                *max_length = Some(4)
+            }
+            ("Order", "Freight", PropertyType::Decimal { precision, .. }) => *precision = Some(6),
+            _ => {}
+        },
+    );
+}
+
+/// Like `generate_to_out_dir`, but with the facets of properties set by `facets`. The parser
+/// doesn't read them from metadata documents yet, so this is the only way to exercise the checks
+/// generated from them. Code generated from the document as is checks nothing, see
+/// `test_validate_parsed_metadata`.
+fn generate_with_facets(
+    metadata_path: &str,
+    file_name: &str,
+    opts: &GeneratorOptions,
+    facets: impl Fn(&str, &mut Property),
+) {
+    println!("cargo:rerun-if-changed={}", metadata_path);
+
+    let source = std::fs::read_to_string(metadata_path).unwrap();
+    let mut project = Edmx::from_str(&source).unwrap();
+    for schema in &mut project.data_services.schemas {
+        for entity in &mut schema.entities {
+            for property in &mut entity.properties {
+                facets(&entity.name, property);
+            }
+        }
+    }
+
+    let out_dir = std::env::var_os("OUT_DIR").unwrap();
+    write_if_changed(
+        Path::new(&out_dir).join(file_name),
+        &generate(&project, opts),
+    )
+    .unwrap();
+}
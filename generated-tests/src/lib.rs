@@ -0,0 +1,496 @@
+//! Code generated from `tests/northwind.xml` and `calendar.xml` by the build script, compiled and
+//! run against real JSON payloads. Each module is generated with a different set of options.
+//!
+//! Exported macros expanded from `include!` can't be named by path within this crate, so the
+//! `$select` macros are exercised from doctests. They accept known property names:
+//!
+//! ```
+//! use generated_tests::{select_macros_northwindmodel_customer, select_macros_northwindmodel_order};
+//!
+//! assert_eq!(select_macros_northwindmodel_order!(OrderID), "OrderID");
+//! assert_eq!(
+//!     select_macros_northwindmodel_order!(OrderID, CustomerID, Freight,),
+//!     "OrderID,CustomerID,Freight"
+//! );
+//! assert_eq!(select_macros_northwindmodel_customer!(CompanyName), "CompanyName");
+//! ```
+//!
+//! and reject unknown ones at compile time:
+//!
+//! ```compile_fail
+//! use generated_tests::select_macros_northwindmodel_order;
+//!
+//! assert_eq!(select_macros_northwindmodel_order!(OrderID, Freigth), "OrderID,Freigth");
+//! ```
+
+/// Overrides the type of Order keys in the `overrides` module. Unlike the `i32` it replaces, it
+/// is neither `Copy` nor `Hash`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OrderNumber(pub i32);
+
+include!(concat!(env!("OUT_DIR"), "/plain.rs"));
+include!(concat!(env!("OUT_DIR"), "/lenient.rs"));
+include!(concat!(env!("OUT_DIR"), "/decimals.rs"));
+include!(concat!(env!("OUT_DIR"), "/patchable.rs"));
+include!(concat!(env!("OUT_DIR"), "/display.rs"));
+include!(concat!(env!("OUT_DIR"), "/events.rs"));
+include!(concat!(env!("OUT_DIR"), "/macros.rs"));
+include!(concat!(env!("OUT_DIR"), "/projections.rs"));
+include!(concat!(env!("OUT_DIR"), "/validation.rs"));
+include!(concat!(env!("OUT_DIR"), "/overrides.rs"));
+include!(concat!(env!("OUT_DIR"), "/unprefixed.rs"));
+
+#[cfg(test)]
+mod tests {
+    use crate::decimals::northwindmodel::Order;
+    use crate::lenient::northwindmodel::Product;
+    use crate::patchable::{northwindmodel::Customer, Patchable};
+    use crate::plain::northwindmodel::Category;
+
+    fn product(discontinued: &str, units_in_stock: &str) -> Result<Product, serde_json::Error> {
+        serde_json::from_str(&format!(
+            r#"{{"ProductID": 1, "ProductName": "Chai", "Discontinued": {}, "UnitsInStock": {}}}"#,
+            discontinued, units_in_stock
+        ))
+    }
+
+    #[test]
+    fn test_lenient_bools() {
+        for (json, expected) in &[
+            ("true", true),
+            ("false", false),
+            ("1", true),
+            ("0", false),
+            ("\"true\"", true),
+            ("\"FALSE\"", false),
+        ] {
+            assert_eq!(
+                product(json, "null").unwrap().discontinued,
+                *expected,
+                "{}",
+                json
+            );
+        }
+
+        for json in &["2", "\"yes\"", "null"] {
+            assert!(product(json, "null").is_err(), "{}", json);
+        }
+    }
+
+    #[test]
+    fn test_lenient_numbers() {
+        for (json, expected) in &[
+            ("17", Some(17)),
+            ("\"17\"", Some(17)),
+            ("\" 17 \"", Some(17)),
+            ("null", None),
+        ] {
+            assert_eq!(
+                product("true", json).unwrap().unitsinstock,
+                *expected,
+                "{}",
+                json
+            );
+        }
+
+        assert_eq!(
+            serde_json::from_str::<Product>(
+                r#"{"ProductID": "7", "ProductName": "Chai", "Discontinued": true}"#
+            )
+            .unwrap()
+            .productid,
+            7
+        );
+
+        for json in &["\"many\"", "\"\"", "\"70000\""] {
+            assert!(product("true", json).is_err(), "{}", json);
+        }
+    }
+
+    fn category(picture: &str) -> Result<Category, serde_json::Error> {
+        serde_json::from_str(&format!(
+            r#"{{"CategoryID": 1, "CategoryName": "Beverages", "Picture": {}}}"#,
+            picture
+        ))
+    }
+
+    #[test]
+    fn test_base64_round_trip() {
+        // One case per padding length, including the empty input.
+        for (bytes, text) in &[
+            (&b""[..], ""),
+            (&b"f"[..], "Zg=="),
+            (&b"fo"[..], "Zm8="),
+            (&b"foo"[..], "Zm9v"),
+            (&b"foob"[..], "Zm9vYg=="),
+            (&b"fooba"[..], "Zm9vYmE="),
+            (&b"foobar"[..], "Zm9vYmFy"),
+            (&[0, 1, 2, 0xfb, 0xff][..], "AAEC+/8="),
+        ] {
+            let category = Category {
+                categoryid: 1,
+                categoryname: "Beverages".to_string(),
+                picture: Some(bytes.to_vec()),
+                ..Default::default()
+            };
+            let json = serde_json::to_string(&category).unwrap();
+            assert!(
+                json.contains(&format!(r#""Picture":"{}""#, text)),
+                "{}",
+                json
+            );
+
+            let decoded: Category = serde_json::from_str(&json).unwrap();
+            assert_eq!(decoded.picture.as_deref(), Some(*bytes));
+        }
+
+        let all: Vec<u8> = (0..=255).collect();
+        let category = Category {
+            picture: Some(all.clone()),
+            ..Default::default()
+        };
+        let json = serde_json::to_string(&category).unwrap();
+        let decoded: Category = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.picture, Some(all));
+    }
+
+    #[test]
+    fn test_base64_variants() {
+        for text in &["\"Zm8=\"", "\"Zm8\"", "\"Z m 8 =\""] {
+            assert_eq!(
+                category(text).unwrap().picture.as_deref(),
+                Some(&b"fo"[..]),
+                "{}",
+                text
+            );
+        }
+
+        assert_eq!(
+            category("\"-_8=\"").unwrap().picture,
+            category("\"+/8=\"").unwrap().picture
+        );
+        assert_eq!(category("null").unwrap().picture, None);
+
+        for text in &["\"Zm9vY\"", "\"Zm*v\"", "[102, 111]"] {
+            assert!(category(text).is_err(), "{}", text);
+        }
+    }
+
+    fn freight(freight: &str) -> Result<Option<i128>, serde_json::Error> {
+        serde_json::from_str::<Order>(&format!(r#"{{"OrderID": 1, "Freight": {}}}"#, freight))
+            .map(|order| order.freight)
+    }
+
+    #[test]
+    fn test_scaled_decimal_parsing() {
+        for (json, expected) in &[
+            ("\"12.34\"", 1234),
+            ("\"-12.34\"", -1234),
+            ("\"+12.34\"", 1234),
+            ("\"7\"", 700),
+            ("\".5\"", 50),
+            ("\"-0.07\"", -7),
+            // Digits beyond the scale are rounded half away from zero.
+            ("\"12.344\"", 1234),
+            ("\"12.345\"", 1235),
+            ("\"-12.345\"", -1235),
+            ("\"0.0049999\"", 0),
+            ("\"-0.005\"", -1),
+            ("\"1e3\"", 100000),
+            ("\"1.5E-2\"", 2),
+            ("\"-2.5e+1\"", -2500),
+            ("\"1e-9\"", 0),
+            ("\"5e-3\"", 1),
+            ("\"-5e-3\"", -1),
+            ("\"5e-4\"", 0),
+            ("\"5e-9\"", 0),
+            ("\"-5e-9\"", 0),
+            ("\"9e-6\"", 0),
+            ("\"0e999999999\"", 0),
+            ("12.5", 1250),
+            ("-3", -300),
+            ("1e3", 100000),
+        ] {
+            assert_eq!(freight(json).unwrap(), Some(*expected), "{}", json);
+        }
+
+        assert_eq!(freight("null").unwrap(), None);
+
+        for json in &[
+            "\"\"",
+            "\"-\"",
+            "\"1.2.3\"",
+            "\"12,34\"",
+            "\"abc\"",
+            "\"1e\"",
+            "\"e3\"",
+            "\"--1\"",
+            "\"1e99\"",
+            "\"1e999999999\"",
+        ] {
+            assert!(freight(json).is_err(), "{}", json);
+        }
+    }
+
+    #[test]
+    fn test_scaled_decimal_formatting() {
+        for (freight, expected) in &[
+            (1234, r#""12.34""#),
+            (-1234, r#""-12.34""#),
+            (7, r#""0.07""#),
+            (-5, r#""-0.05""#),
+            (0, r#""0.00""#),
+            (100000, r#""1000.00""#),
+            (i128::MIN, r#""-1701411834604692317316873037158841057.28""#),
+        ] {
+            let order = Order {
+                orderid: 1,
+                freight: Some(*freight),
+                ..Default::default()
+            };
+            let json = serde_json::to_string(&order).unwrap();
+            assert!(
+                json.contains(&format!(r#""Freight":{}"#, expected)),
+                "{}",
+                json
+            );
+
+            let decoded: Order = serde_json::from_str(&json).unwrap();
+            assert_eq!(decoded.freight, Some(*freight));
+        }
+    }
+
+    #[test]
+    fn test_patchable_serialization() {
+        let customer = |contactname| Customer {
+            customerid: "ALFKI".to_string(),
+            companyname: "Alfreds Futterkiste".to_string(),
+            contactname,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            serde_json::to_string(&customer(Patchable::Missing)).unwrap(),
+            r#"{"CustomerID":"ALFKI","CompanyName":"Alfreds Futterkiste","Orders":[]}"#
+        );
+        assert_eq!(
+            serde_json::to_string(&customer(Patchable::Null)).unwrap(),
+            r#"{"CustomerID":"ALFKI","CompanyName":"Alfreds Futterkiste","ContactName":null,"Orders":[]}"#
+        );
+        assert_eq!(
+            serde_json::to_string(&customer(Patchable::Value("Maria".to_string()))).unwrap(),
+            r#"{"CustomerID":"ALFKI","CompanyName":"Alfreds Futterkiste","ContactName":"Maria","Orders":[]}"#
+        );
+    }
+
+    #[test]
+    fn test_patchable_deserialization() {
+        let contactname = |json: &str| {
+            serde_json::from_str::<Customer>(&format!(
+                r#"{{"CustomerID": "ALFKI", "CompanyName": "Alfreds Futterkiste"{}}}"#,
+                json
+            ))
+            .unwrap()
+            .contactname
+        };
+
+        assert_eq!(contactname(""), Patchable::Missing);
+        assert_eq!(contactname(r#", "ContactName": null"#), Patchable::Null);
+        assert_eq!(
+            contactname(r#", "ContactName": "Maria""#),
+            Patchable::Value("Maria".to_string())
+        );
+    }
+
+    #[test]
+    fn test_display() {
+        use crate::display::northwindmodel::{Customer, CustomerId, Order, OrderId};
+
+        let order = Order {
+            orderid: OrderId(10248),
+            ..Default::default()
+        };
+        assert_eq!(order.to_string(), "Order(10248)");
+
+        let customer = Customer {
+            customerid: CustomerId("O'Brien".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(customer.to_string(), "Customer('O''Brien')");
+
+        let event = crate::events::calendar::Event {
+            start: std::time::Duration::from_millis(1500),
+            ..Default::default()
+        };
+        assert_eq!(event.to_string(), "Event(1.5s)");
+    }
+
+    /// Names of the properties serialized from a projection, in `$select` order.
+    fn serialized_names<T: serde::Serialize>(projection: &T, select: &str) -> Vec<String> {
+        let json = serde_json::to_string(projection).unwrap();
+        let object: std::collections::HashMap<String, serde_json::Value> =
+            serde_json::from_str(&json).unwrap();
+        let mut names: Vec<_> = object.into_keys().collect();
+        names.sort_by_key(|name| select.split(',').position(|selected| selected == name));
+        names
+    }
+
+    #[test]
+    fn test_projections() {
+        use crate::projections::northwindmodel::{CustomerProjection, OrderProjection};
+
+        let customer: CustomerProjection =
+            serde_json::from_str(r#"{"CompanyName": "Alfreds Futterkiste", "ContactName": null}"#)
+                .unwrap();
+        assert_eq!(customer.company_name, "Alfreds Futterkiste");
+        assert_eq!(customer.contact_name, None);
+        assert_eq!(CustomerProjection::SELECT, "CompanyName,ContactName");
+        assert_eq!(
+            serialized_names(&customer, CustomerProjection::SELECT).join(","),
+            CustomerProjection::SELECT
+        );
+
+        let order: OrderProjection = serde_json::from_str(
+            r#"{"OrderID": 10248, "OrderDate": "1996-07-04T00:00:00", "Freight": 32.38}"#,
+        )
+        .unwrap();
+        assert_eq!(order.order_id, 10248);
+        assert_eq!(order.freight, Some(32.38));
+        assert_eq!(OrderProjection::SELECT, "OrderID,OrderDate,Freight");
+        assert_eq!(
+            serialized_names(&order, OrderProjection::SELECT).join(","),
+            OrderProjection::SELECT
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        use crate::validation::{
+            northwindmodel::{Category, Customer, Order},
+            ValidationError,
+        };
+
+        let mut customer = Customer {
+            customerid: "ALFKI".to_string(),
+            contactname: Some("Maria Anders".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(customer.validate(), Ok(()));
+
+        // Lengths are counted in characters rather than bytes.
+        customer.customerid = "ÆØÅÆØ".to_string();
+        assert_eq!(customer.validate(), Ok(()));
+
+        customer.customerid = "ALFKI2".to_string();
+        customer.contactname = Some("x".repeat(31));
+        let errors = customer.validate().unwrap_err();
+        assert_eq!(
+            errors,
+            vec![
+                ValidationError::TooLong {
+                    property: "CustomerID",
+                    max_length: 5,
+                    length: 6
+                },
+                ValidationError::TooLong {
+                    property: "ContactName",
+                    max_length: 30,
+                    length: 31
+                },
+            ]
+        );
+        assert_eq!(
+            errors[0].to_string(),
+            "CustomerID is 6 long, exceeding its MaxLength of 5"
+        );
+
+        let category = Category {
+            picture: Some(vec![0; 5]),
+            ..Default::default()
+        };
+        assert_eq!(
+            category.validate(),
+            Err(vec![ValidationError::TooLong {
+                property: "Picture",
+                max_length: 4,
+                length: 5
+            }])
+        );
+
+        // A Precision of 6 with a scale of 2 leaves four integer digits.
+        let freight = |freight: &str| {
+            serde_json::from_str::<Order>(&format!(r#"{{"OrderID": 1, "Freight": "{}"}}"#, freight))
+                .unwrap()
+                .validate()
+        };
+        assert_eq!(freight("9999.99"), Ok(()));
+        assert_eq!(freight("-9999.99"), Ok(()));
+        assert_eq!(
+            freight("10000"),
+            Err(vec![ValidationError::TooPrecise {
+                property: "Freight",
+                precision: 6
+            }])
+        );
+        assert_eq!(
+            freight("-10000.00").unwrap_err()[0].to_string(),
+            "Freight has more digits than its Precision of 6"
+        );
+    }
+
+    #[test]
+    fn test_validate_parsed_metadata() {
+        // MaxLength isn't read from the document, so nothing is checked.
+        let customer = crate::plain::northwindmodel::Customer {
+            customerid: "TOOLONG".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(customer.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_to_json() {
+        use crate::plain::OpenDataModel;
+
+        let category = Category {
+            categoryid: 1,
+            categoryname: "Beverages".to_string(),
+            ..Default::default()
+        };
+        let json = category.to_json().unwrap();
+        assert_eq!(
+            json.get("CategoryName").and_then(|name| name.as_str()),
+            Some("Beverages")
+        );
+        assert_eq!(json.get("categoryname"), None);
+    }
+
+    #[test]
+    fn test_unprefixed() {
+        use crate::northwindmodel::{AnyEntity, Customer};
+
+        let customer = Customer {
+            CustomerID: "ALFKI".to_string(),
+            ContactName: crate::Patchable::Null,
+            ..Default::default()
+        };
+        assert_eq!(customer.Phone, crate::Patchable::Missing);
+
+        let entity = AnyEntity::Customer(customer);
+        assert!(matches!(entity, AnyEntity::Customer(_)));
+    }
+
+    #[test]
+    fn test_overridden_key() {
+        use crate::overrides::northwindmodel::{Order, OrderKey};
+
+        let order: Order = serde_json::from_str(r#"{"OrderID": 10248}"#).unwrap();
+        assert_eq!(
+            OrderKey::from(&order),
+            OrderKey {
+                orderid: crate::OrderNumber(10248)
+            }
+        );
+    }
+}
@@ -0,0 +1,6084 @@
+use codegen::{Field, Function, Scope, Trait};
+use indoc::indoc;
+use odata_parser_rs::{Edmx, EntityType, NavigationProperty, Property, PropertyType, Schema};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+/// Options controlling the generated code.
+#[derive(Debug, Clone, Default)]
+pub struct GeneratorOptions {
+    /// Don't derive Serialize and Deserialize traits to all structs
+    pub no_serde: bool,
+
+    /// Don't coerce empty strings into None when deserializing into Option<String>
+    pub no_empty_string_is_null: bool,
+
+    /// Don't produce OpenDataModel traits and implementations for run-time reflection
+    pub no_reflection: bool,
+
+    /// Don't include NavigationProperties in the output structures. This makes deserializing
+    /// $expand-ed properties impossible.
+    pub no_expand: bool,
+
+    /// Put a single serde(rename_all) on each struct using the detected naming convention, only
+    /// renaming fields which don't follow it
+    pub rename_all: bool,
+
+    /// Also coerce empty strings into None when deserializing nullable non-string properties,
+    /// such as numbers and dates
+    pub empty_values_are_null: bool,
+
+    /// Accept "true"/"false" strings and 0/1 integers when deserializing Boolean properties
+    pub lenient_bools: bool,
+
+    /// Accept numbers encoded as strings when deserializing numeric properties
+    pub lenient_numbers: bool,
+
+    /// How property names are turned into field names, one of `original`, `snake` or `lower`.
+    /// Defaults to `lower`
+    pub field_case: Option<String>,
+
+    /// Generate an AnyEntity enum in each schema with a variant for every entity type
+    pub any_entity: bool,
+
+    /// Rust type generated for DateTime properties, one of `chrono-naive`, `chrono-utc` or
+    /// `time`. Defaults to `chrono-naive`. `time` (de)serializes through RFC 3339, which requires
+    /// its `serde-well-known` feature
+    pub datetime_type: Option<String>,
+
+    /// Rust type generated for Decimal properties, one of `f64`, `i128` or `rust-decimal`.
+    /// Defaults to `f64`. `i128` holds the value multiplied by 10^`decimal_scale`, rounding
+    /// digits beyond the scale half away from zero when deserializing
+    pub decimal_type: Option<String>,
+
+    /// Number of fractional digits kept in `i128` decimals. Defaults to 0, the default Scale of
+    /// Edm.Decimal
+    pub decimal_scale: Option<u32>,
+
+    /// Generate a <Entity>Key struct for each entity, along with a conversion from the entity
+    pub key_structs: bool,
+
+    /// Generate collection_url and url functions on each entity in an entity set, building OData
+    /// URLs for the set and for a single entity by key. Implies `key_structs`
+    pub url_builders: bool,
+
+    /// Generate a test module in each schema, checking that default-constructed entities survive
+    /// a round trip through serde_json. Entities derive PartialEq in tests, so types from
+    /// `type_overrides` must implement it
+    pub emit_tests: bool,
+
+    /// Generate navigations to a single required entity as Box<T> rather than Option<Box<T>>. Such
+    /// navigations must then always be $expand-ed
+    pub required_navigations: bool,
+
+    /// Pointer type wrapping navigated entities, one of `box`, `arc` or `rc`. Defaults to `box`.
+    /// With `arc` and `rc`, collections hold pointers too, so entities can be shared after
+    /// deserializing. This requires serde's `rc` feature, and doesn't preserve sharing between
+    /// entities when serializing or deserializing
+    pub nav_ptr: Option<String>,
+
+    /// Generate accessor methods for navigations, iterating collections and borrowing single
+    /// related entities
+    pub nav_accessors: bool,
+
+    /// Generate a prelude module re-exporting the entity types, key structs and reflection traits
+    /// of every schema
+    pub prelude: bool,
+
+    /// Implement Display on entities, showing the entity name and its key, e.g. `Order(10248)`
+    pub display: bool,
+
+    /// Wrap the key property of each entity in a <Entity>Id newtype, so keys of different
+    /// entities can't be mixed up
+    pub typed_ids: bool,
+
+    /// Implement PartialEq on structs by comparing only their properties, ignoring
+    /// NavigationProperties
+    pub eq_ignores_navigations: bool,
+
+    /// Generate a fuzz_deserialize function for exercising deserialization of every entity set, gated
+    /// behind the "fuzzing" feature
+    pub fuzzing: bool,
+
+    /// Move NavigationProperties out of the entity structs and into separate <Entity>Expanded structs
+    pub group_navigations: bool,
+
+    /// Document each struct with the OData namespace and entity set it originates from
+    pub namespace_docs: bool,
+
+    /// Leading namespace (e.g. Com.Example) to drop from schema namespaces when generating module
+    /// paths
+    pub strip_prefix: Option<String>,
+
+    /// Generate nullable properties as Patchable<T>, distinguishing missing properties from explicit
+    /// nulls
+    pub triple_state_options: bool,
+
+    /// Generate ODataResponse<T> and ODataV2Response<T> types for deserializing collection responses
+    pub envelope: bool,
+
+    /// Generate a marker type for each entity set, implementing the EntitySet trait with the path,
+    /// entity type and key type of the set
+    pub entity_set_markers: bool,
+
+    /// Generate a decode_collection function on each entity in an entity set, decoding v4 or v2
+    /// collection responses. Implies `envelope`
+    pub decode_collections: bool,
+
+    /// Visibility of all generated modules, items, fields and re-exports, either `pub` or `pub(crate)`.
+    /// Defaults to `pub`
+    pub visibility: Option<String>,
+
+    /// Generate a select_<module path>_<entity>! macro for each entity, such as
+    /// `select_northwindmodel_order!`, building a $select string from property names checked at
+    /// compile time
+    pub select_macros: bool,
+
+    /// Sort the generated entity set re-exports
+    pub sort_imports: bool,
+
+    /// Keep schemas, entities, entity sets and reflection tables in document order instead of
+    /// sorting them by name
+    pub unsorted: bool,
+
+    /// Header comment to put at the top of the output instead of the default one
+    pub header: Option<String>,
+
+    /// Don't put a header comment at the top of the output
+    pub no_header: bool,
+
+    /// Emit an SPDX-License-Identifier line with the given license expression at the top of the
+    /// output
+    pub spdx: Option<String>,
+
+    /// Allow the lints the selected options are known to trigger in the generated code, on the
+    /// prefix module if there is one and on every item at the root of the output otherwise
+    pub emit_allow: bool,
+
+    /// Wrap all generated code in a single public module with the given name
+    pub module_prefix: Option<String>,
+
+    /// Don't re-export the contents of the default schema from the root through a glob import
+    pub no_glob_reexport: bool,
+
+    /// Put the items of the only schema directly at the root, instead of in a module named
+    /// after its namespace. Has no effect when there are several schemas
+    pub flatten_single_schema: bool,
+
+    /// Which serde traits to derive on generated structs, one of `de`, `ser` or `both`. Defaults
+    /// to `both`
+    pub serde_mode: Option<String>,
+
+    /// Don't serialize key properties, for creating entities whose keys are assigned by the service
+    pub skip_serializing_keys: bool,
+
+    /// Don't serialize optional properties and navigations which are None, rather than sending
+    /// them as null
+    pub skip_none: bool,
+
+    /// Generate a <Entity>Patch struct for each entity with every property optional, serializing
+    /// only the properties which are set
+    pub patch_structs: bool,
+
+    /// Properties to keep in a <Entity>Projection struct, for requesting only some of an entity's
+    /// properties with `$select`. Keyed by entity name, optionally qualified by its namespace.
+    /// Unknown property names are reported by `check_options`
+    pub projections: HashMap<String, Vec<String>>,
+
+    /// Generate a validate method on each entity, checking strings and binary values against their
+    /// MaxLength and decimals against their Precision. f64 decimals are only checked for the
+    /// digits before the decimal point. odata-parser-rs doesn't read these facets from metadata
+    /// documents yet, so nothing is checked unless they are set on the parsed `Edmx` by hand
+    pub validate: bool,
+
+    /// Mark entity, <Entity>Expanded and <Entity>Patch structs as #[non_exhaustive], so other
+    /// crates can't construct them with struct literals
+    pub non_exhaustive: bool,
+
+    /// Add a to_json method to OpenDataModel, serializing entities into a serde_json::Value keyed
+    /// by the OData property names. Has no effect without reflection or Serialize derives
+    pub to_json: bool,
+
+    /// Rust types to use for specific properties, keyed by their qualified name
+    /// (`Namespace.Entity.Property`)
+    pub type_overrides: HashMap<String, TypeOverride>,
+}
+
+/// A user-provided Rust type for a property, replacing the type the generator would pick.
+#[derive(Debug, Clone)]
+pub struct TypeOverride {
+    /// Rust type of the field, emitted verbatim. Nullable properties still wrap it in `Option`.
+    pub rust_type: String,
+
+    /// Module to (de)serialize the field with, through `serde(with)`
+    pub serde_with: Option<String>,
+}
+
+/// Items the generator may define at the root of the output, next to the schema modules.
+const ROOT_ITEMS: [&str; 39] = [
+    "empty_string_as_none",
+    "base64_encode",
+    "base64_decode",
+    "serialize_base64",
+    "serialize_optional_base64",
+    "deserialize_base64",
+    "deserialize_optional_base64",
+    "BASE64_ALPHABET",
+    "DECIMAL_SCALE",
+    "DecimalText",
+    "format_scaled_decimal",
+    "parse_scaled_decimal",
+    "serialize_scaled_decimal",
+    "serialize_optional_scaled_decimal",
+    "deserialize_scaled_decimal",
+    "deserialize_optional_scaled_decimal",
+    "EmptyValue",
+    "empty_value_as_none",
+    "LenientBool",
+    "lenient_bool",
+    "lenient_optional_bool",
+    "LenientNumber",
+    "lenient_number",
+    "lenient_optional_number",
+    "Patchable",
+    "ValidationError",
+    "ODataResponse",
+    "ODataV2Response",
+    "ODataV2Results",
+    "OpenDataModel",
+    "OpenDataType",
+    "OpenDataValue",
+    "ModelInfo",
+    "model_for",
+    "expand_paths",
+    "EntitySetName",
+    "EntitySet",
+    "fuzz_deserialize",
+    "prelude",
+];
+
+/// serde `rename_all` conventions considered when detecting the naming convention of an entity.
+const RENAME_RULES: [&str; 3] = ["PascalCase", "camelCase", "UPPERCASE"];
+
+/// Mirrors serde's `rename_all` behaviour for a snake_case field name.
+fn apply_rename_rule(rule: &str, field: &str) -> String {
+    match rule {
+        "UPPERCASE" => field.to_ascii_uppercase(),
+        "PascalCase" | "camelCase" => {
+            let mut pascal = String::new();
+            let mut capitalize = true;
+            for ch in field.chars() {
+                if ch == '_' {
+                    capitalize = true;
+                } else if capitalize {
+                    pascal.push(ch.to_ascii_uppercase());
+                    capitalize = false;
+                } else {
+                    pascal.push(ch);
+                }
+            }
+
+            if rule == "camelCase" {
+                let mut chars = pascal.chars();
+                chars
+                    .next()
+                    .map(|first| first.to_ascii_lowercase().to_string() + chars.as_str())
+                    .unwrap_or_default()
+            } else {
+                pascal
+            }
+        }
+        _ => field.to_string(),
+    }
+}
+
+/// Picks the `rename_all` convention which covers the most property and navigation names of
+/// the entity, if any covers more names than leaving the field names as they are.
+fn detect_rename_rule(entity: &EntityType, opts: &GeneratorOptions) -> Option<&'static str> {
+    let names: Vec<(&str, String)> = entity
+        .properties
+        .iter()
+        .map(|property| property.name.as_str())
+        .chain(entity.navigations.iter().map(|nav| nav.name.as_str()))
+        .zip(field_names(entity, opts))
+        .collect();
+
+    let covered = |rule: &str| {
+        names
+            .iter()
+            .filter(|(name, field)| {
+                apply_rename_rule(rule, field.trim_start_matches("r#")) == *name
+            })
+            .count()
+    };
+
+    let unchanged = covered("unchanged");
+    RENAME_RULES
+        .iter()
+        .map(|rule| (*rule, covered(rule)))
+        .filter(|(_, count)| *count > unchanged)
+        .max_by_key(|(_, count)| *count)
+        .map(|(rule, _)| rule)
+}
+
+/// Converts a PascalCase or camelCase name to snake_case, keeping acronyms such as the `ID` in
+/// `CustomerID` together.
+fn to_snake_case(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut snake = String::new();
+
+    for (i, &ch) in chars.iter().enumerate() {
+        if ch.is_uppercase() && i > 0 && chars[i - 1] != '_' {
+            let after_lower = chars[i - 1].is_lowercase() || chars[i - 1].is_numeric();
+            let acronym_end = chars[i - 1].is_uppercase()
+                && matches!(chars.get(i + 1), Some(next) if next.is_lowercase());
+
+            if after_lower || acronym_end {
+                snake.push('_');
+            }
+        }
+        snake.extend(ch.to_lowercase());
+    }
+
+    snake
+}
+
+/// Names of the struct fields generated for the properties and then the navigations of an
+/// entity. Names are converted according to `field_case` and escaped if they collide with a
+/// keyword, and names which collide once converted are suffixed with their position among the
+/// colliding names.
+fn field_names(entity: &EntityType, opts: &GeneratorOptions) -> Vec<String> {
+    let mut seen: HashMap<String, usize> = HashMap::new();
+
+    entity
+        .properties
+        .iter()
+        .map(|property| &property.name)
+        .chain(entity.navigations.iter().map(|nav| &nav.name))
+        .map(|name| {
+            let name = match opts.field_case.as_deref() {
+                Some("original") => name.to_string(),
+                Some("snake") => to_snake_case(name),
+                _ => name.to_lowercase(),
+            };
+            let count = seen.entry(name.clone()).or_insert(0);
+            *count += 1;
+
+            match *count {
+                1 => escape_keyword(&name),
+                n => format!("{}_{}", name, n),
+            }
+        })
+        .collect()
+}
+
+/// Name of the struct field generated for a property of the entity.
+fn property_field_name(
+    entity: &EntityType,
+    property: &Property,
+    opts: &GeneratorOptions,
+) -> String {
+    let index = entity
+        .properties
+        .iter()
+        .position(|other| other.name == property.name)
+        .expect("property belongs to the entity");
+
+    field_names(entity, opts).swap_remove(index)
+}
+
+/// Name of the struct field generated for a navigation of the entity.
+fn navigation_field_name(
+    entity: &EntityType,
+    navigation: &NavigationProperty,
+    opts: &GeneratorOptions,
+) -> String {
+    let index = entity
+        .navigations
+        .iter()
+        .position(|other| other.name == navigation.name)
+        .expect("navigation belongs to the entity");
+
+    field_names(entity, opts).swap_remove(entity.properties.len() + index)
+}
+
+/// Items in the order they are generated, sorted by name unless document order is to be kept.
+fn ordered<'a, T>(
+    items: &'a [T],
+    name: impl Fn(&T) -> &str,
+    opts: &GeneratorOptions,
+) -> Vec<&'a T> {
+    let mut items: Vec<&T> = items.iter().collect();
+    if !opts.unsorted {
+        items.sort_by(|a, b| name(a).cmp(name(b)));
+    }
+    items
+}
+
+/// Whether a default-constructed entity can be serialized and deserialized back by the tests
+/// generated with `emit_tests`.
+fn round_trips(namespace: &str, entity: &EntityType, opts: &GeneratorOptions) -> bool {
+    derives_serialize(opts)
+        && derives_deserialize(opts)
+        && !opts.skip_serializing_keys
+        && entity.properties.iter().all(|property| {
+            // Neither user-provided types nor time's OffsetDateTime are known to implement Default.
+            type_override(namespace, entity, property, opts).is_none()
+                && !(opts.datetime_type.as_deref() == Some("time")
+                    && matches!(property.inner, PropertyType::DateTime { .. })
+                    && !is_nullable(entity, property))
+        })
+}
+
+/// Whether the Rust type generated for the property implements `Copy`.
+fn is_copy(property: &Property) -> bool {
+    !matches!(
+        property.inner,
+        PropertyType::Binary { .. } | PropertyType::String { .. }
+    )
+}
+
+/// How a property type is represented in the generated code.
+struct TypeMapping {
+    /// Rust type of the generated field, before any `Option` wrapping.
+    rust_type: &'static str,
+    /// Name of the `OpenDataType` reflection variant.
+    variant: &'static str,
+    /// Fields of the reflection variant besides `nullable` and `key`, as (name, type, value).
+    facets: Vec<(&'static str, &'static str, String)>,
+}
+
+fn type_mapping(property_type: &PropertyType) -> TypeMapping {
+    let (rust_type, variant, facets) = match property_type {
+        PropertyType::Binary { .. } => ("Vec<u8>", "Binary", vec![]),
+        PropertyType::Boolean { .. } => ("bool", "Boolean", vec![]),
+        PropertyType::Byte { .. } => ("u8", "Byte", vec![]),
+        PropertyType::DateTime { .. } => ("chrono::NaiveDateTime", "DateTime", vec![]),
+        PropertyType::DateTimeOffset { .. } => ("std::time::Duration", "DateTimeOffset", vec![]),
+        PropertyType::Decimal { precision, .. } => (
+            "f64",
+            "Decimal",
+            vec![("precision", "Option<u8>", format!("{:?}", precision))],
+        ),
+        PropertyType::Double { .. } => ("f64", "Double", vec![]),
+        PropertyType::Int16 { .. } => ("i16", "Int16", vec![]),
+        PropertyType::Int32 { .. } => ("i32", "Int32", vec![]),
+        PropertyType::String {
+            max_length,
+            fixed_length,
+            ..
+        } => (
+            "String",
+            "String",
+            vec![
+                ("max_length", "Option<u32>", format!("{:?}", max_length)),
+                ("fixed_length", "Option<u32>", format!("{:?}", fixed_length)),
+            ],
+        ),
+    };
+
+    TypeMapping {
+        rust_type,
+        variant,
+        facets,
+    }
+}
+
+/// One instance of every property type, from which the `OpenDataType` enum is generated.
+fn property_type_prototypes() -> Vec<PropertyType> {
+    vec![
+        PropertyType::Binary {
+            max_length: None,
+            fixed_length: None,
+            default: None,
+        },
+        PropertyType::Boolean { default: None },
+        PropertyType::Byte {
+            precision: None,
+            default: None,
+        },
+        PropertyType::DateTime {
+            precision: None,
+            default: None,
+        },
+        PropertyType::DateTimeOffset {
+            precision: None,
+            default: None,
+        },
+        PropertyType::Decimal {
+            precision: None,
+            default: None,
+        },
+        PropertyType::Double {
+            precision: None,
+            default: None,
+        },
+        PropertyType::Int16 {
+            precision: None,
+            default: None,
+        },
+        PropertyType::Int32 {
+            precision: None,
+            default: None,
+        },
+        PropertyType::String {
+            precision: None,
+            max_length: None,
+            fixed_length: None,
+        },
+    ]
+}
+
+/// The OData literal format of a key property, along with the transformation applied to its
+/// value before formatting it, or `None` if keys of its type can't be put in URLs.
+fn key_literal(
+    property: &Property,
+    opts: &GeneratorOptions,
+) -> Option<(&'static str, &'static str)> {
+    match property.inner {
+        // Scaled decimals would need to be formatted back into their fractional form.
+        PropertyType::Decimal { .. } if scales_decimals(opts) => None,
+        PropertyType::String { .. } => Some(("'{}'", ".replace('\\'', \"''\")")),
+        PropertyType::Boolean { .. }
+        | PropertyType::Byte { .. }
+        | PropertyType::Int16 { .. }
+        | PropertyType::Int32 { .. } => Some(("{}", "")),
+        PropertyType::Decimal { .. } => Some(("{}M", "")),
+        PropertyType::Double { .. } => Some(("{}d", "")),
+        _ => None,
+    }
+}
+
+/// Whether the Rust type generated for the property implements `Eq` and `Hash`.
+fn is_hashable(property: &Property) -> bool {
+    !matches!(
+        property.inner,
+        PropertyType::Decimal { .. } | PropertyType::Double { .. }
+    )
+}
+
+/// Whether the property is the key of its entity.
+fn is_key(entity: &EntityType, property: &Property) -> bool {
+    entity.key.property_ref.name == property.name
+}
+
+/// Whether the property is generated as optional. Key properties can never be null, even when
+/// the metadata document marks them as nullable. Both the struct fields and the reflection
+/// metadata are derived from this.
+fn is_nullable(entity: &EntityType, property: &Property) -> bool {
+    property.nullable && !is_key(entity, property)
+}
+
+/// Whether the property is the key of its entity and generated as a <Entity>Id newtype.
+fn is_typed_id(entity: &EntityType, property: &Property, opts: &GeneratorOptions) -> bool {
+    opts.typed_ids && is_key(entity, property)
+}
+
+/// Rust type generated for a property type, with DateTime and Decimal mapped to the selected
+/// datetime and decimal types.
+fn rust_type(property_type: &PropertyType, opts: &GeneratorOptions) -> &'static str {
+    match (property_type, opts.datetime_type.as_deref()) {
+        (PropertyType::DateTime { .. }, Some("chrono-utc")) => "chrono::DateTime<chrono::Utc>",
+        (PropertyType::DateTime { .. }, Some("time")) => "time::OffsetDateTime",
+        (PropertyType::Decimal { .. }, _) => match opts.decimal_type.as_deref() {
+            Some("i128") => "i128",
+            Some("rust-decimal") => "rust_decimal::Decimal",
+            _ => type_mapping(property_type).rust_type,
+        },
+        _ => type_mapping(property_type).rust_type,
+    }
+}
+
+/// Whether Decimal properties are generated as i128 scaled by `decimal_scale`.
+fn scales_decimals(opts: &GeneratorOptions) -> bool {
+    opts.decimal_type.as_deref() == Some("i128")
+}
+
+/// The user-provided type for the property, if any.
+fn type_override<'a>(
+    namespace: &str,
+    entity: &EntityType,
+    property: &Property,
+    opts: &'a GeneratorOptions,
+) -> Option<&'a TypeOverride> {
+    opts.type_overrides
+        .get(&format!("{}.{}.{}", namespace, entity.name, property.name))
+}
+
+/// Rust type generated for a property, before any `Option` wrapping.
+fn property_rust_type(
+    namespace: &str,
+    entity: &EntityType,
+    property: &Property,
+    opts: &GeneratorOptions,
+) -> String {
+    match type_override(namespace, entity, property, opts) {
+        Some(type_override) => type_override.rust_type.clone(),
+        None => rust_type(&property.inner, opts).to_string(),
+    }
+}
+
+fn edm_type_to_rust_type(
+    namespace: &str,
+    entity: &EntityType,
+    property: &Property,
+    opts: &GeneratorOptions,
+) -> String {
+    if is_typed_id(entity, property, opts)
+        && type_override(namespace, entity, property, opts).is_none()
+    {
+        return format!("{}Id", entity.name);
+    }
+
+    let inner = property_rust_type(namespace, entity, property, opts);
+
+    if is_nullable(entity, property) {
+        format!("Option<{}>", inner)
+    } else {
+        inner.to_string()
+    }
+}
+
+fn entity_type_reflection(
+    entity: &EntityType,
+    crate_path: &str,
+    opts: &GeneratorOptions,
+) -> String {
+    let fields: Vec<(_, _)> = ordered(&entity.properties, |property| &property.name, opts)
+        .into_iter()
+        .map(|property| {
+            let mapping = type_mapping(&property.inner);
+            let facets: String = mapping
+                .facets
+                .iter()
+                .map(|(name, _, value)| format!(", {}: {}", name, value))
+                .collect();
+
+            let typename = format!(
+                "{} {{ nullable: {}, key: {}{} }}",
+                mapping.variant,
+                is_nullable(entity, property),
+                is_key(entity, property),
+                facets
+            );
+
+            (property.name.clone(), typename)
+        })
+        .collect();
+
+    format!(
+        "&[{}]",
+        fields
+            .iter()
+            .map(|field| {
+                format!(
+                    "({}, {}::OpenDataType::{})",
+                    string_literal(&field.0),
+                    crate_path,
+                    field.1
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    )
+}
+
+/// Module path segments generated for a schema namespace, with `strip_prefix` removed from the
+/// front of it. Namespaces which would be stripped entirely are left untouched.
+fn module_segments(namespace: &str, strip_prefix: Option<&str>) -> VecDeque<String> {
+    strip_prefix
+        .and_then(|prefix| namespace.strip_prefix(prefix))
+        .and_then(|rest| rest.strip_prefix('.'))
+        .unwrap_or(namespace)
+        .split('.')
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Path to the module generated for a schema namespace, or the root itself if the only schema
+/// is flattened into it.
+fn module_path(
+    crate_path: &str,
+    namespace: &str,
+    strip_prefix: Option<&str>,
+    flatten: bool,
+) -> String {
+    if flatten {
+        return crate_path.to_string();
+    }
+
+    module_segments(namespace, strip_prefix)
+        .iter()
+        .fold(crate_path.to_string(), |path, segment| {
+            path + "::" + segment
+        })
+}
+
+/// Path to the generated struct for a namespace-qualified entity type name.
+fn entity_type_path(
+    crate_path: &str,
+    qualified_name: &str,
+    strip_prefix: Option<&str>,
+    flatten: bool,
+) -> String {
+    let (namespace, name) = qualified_name
+        .rsplit_once('.')
+        .unwrap_or(("", qualified_name));
+
+    format!(
+        "{}::{}",
+        module_path(crate_path, namespace, strip_prefix, flatten),
+        name
+    )
+}
+
+/// Maps namespace-qualified entity type names to the name of the first entity set containing them.
+fn entity_set_names(project: &Edmx) -> HashMap<&str, &str> {
+    let mut names = HashMap::new();
+
+    for schema in &project.data_services.schemas {
+        for set in schema.entity_sets().into_iter().flatten() {
+            names
+                .entry(set.entity_type.as_str())
+                .or_insert_with(|| set.name.as_str());
+        }
+    }
+
+    names
+}
+
+/// Smart pointer wrapping navigated entities, or `None` for the default `Box`.
+fn shared_nav_ptr(opts: &GeneratorOptions) -> Option<&'static str> {
+    match opts.nav_ptr.as_deref() {
+        Some("arc") => Some("std::sync::Arc"),
+        Some("rc") => Some("std::rc::Rc"),
+        _ => None,
+    }
+}
+
+/// Cardinality of a navigation as reported by reflection: "one", "optional" or "many".
+fn cardinality(multiplicity: &str) -> &'static str {
+    match multiplicity {
+        "1" => "one",
+        "0..1" => "optional",
+        _ => "many",
+    }
+}
+
+/// Resolves the namespace-qualified entity type and multiplicity targeted by a navigation,
+/// searching the associations of every schema in the document, starting with its own.
+fn lookup_entity_type(
+    project: &Edmx,
+    schema: &Schema,
+    navigation_property: &NavigationProperty,
+) -> Option<(String, String)> {
+    let association_name = navigation_property
+        .relationship
+        .rsplit('.')
+        .next()
+        .unwrap_or(&navigation_property.relationship);
+
+    let schemas = std::iter::once(schema).chain(
+        project
+            .data_services
+            .schemas
+            .iter()
+            .filter(|other| !std::ptr::eq(*other, schema)),
+    );
+
+    for association in schemas.flat_map(|schema| &schema.associations) {
+        if association.name != association_name {
+            continue;
+        }
+
+        for end in &association.ends {
+            if end.role.as_ref() != Some(&navigation_property.to_role) {
+                continue;
+            }
+
+            let entity_type = end.entity_type.as_ref()?;
+            let (namespace, name) = entity_type.rsplit_once('.')?;
+            let exists = project.data_services.schemas.iter().any(|target| {
+                target.namespace == namespace
+                    && target.entities.iter().any(|entity| entity.name == name)
+            });
+
+            return match (exists, &end.multiplicity) {
+                (true, Some(multiplicity)) => Some((entity_type.clone(), multiplicity.clone())),
+                _ => None,
+            };
+        }
+    }
+
+    None
+}
+
+/// Serde traits derived on generated structs, as selected by `--serde-mode`.
+fn serde_derives(opts: &GeneratorOptions) -> Option<String> {
+    let derives = match opts.serde_mode.as_deref() {
+        _ if opts.no_serde => return None,
+        Some("de") => "serde::Deserialize",
+        Some("ser") => "serde::Serialize",
+        _ => "serde::Serialize, serde::Deserialize",
+    };
+
+    Some(format!(
+        "#[cfg_attr(feature = \"serde\", derive({}))]",
+        derives
+    ))
+}
+
+/// Whether generated structs implement `Deserialize`.
+fn derives_deserialize(opts: &GeneratorOptions) -> bool {
+    !opts.no_serde && opts.serde_mode.as_deref() != Some("ser")
+}
+
+/// Attribute omitting `None` values when serializing.
+const SKIP_NONE: &str =
+    "#[cfg_attr(feature = \"serde\", serde(skip_serializing_if = \"Option::is_none\"))]";
+
+/// Whether generated structs implement `Serialize`.
+fn derives_serialize(opts: &GeneratorOptions) -> bool {
+    !opts.no_serde && opts.serde_mode.as_deref() != Some("de")
+}
+
+/// Whether any property of an `adapted` type is (de)serialized with the required and the optional
+/// root helpers, respectively.
+fn adapted_properties(
+    project: &Edmx,
+    opts: &GeneratorOptions,
+    adapted: impl Fn(&PropertyType) -> bool,
+) -> (bool, bool) {
+    let mut uses = (false, false);
+
+    for schema in &project.data_services.schemas {
+        for entity in &schema.entities {
+            for property in &entity.properties {
+                let patchable = opts.triple_state_options && is_nullable(entity, property);
+                if !adapted(&property.inner)
+                    || patchable
+                    || is_typed_id(entity, property, opts)
+                    || type_override(&schema.namespace, entity, property, opts).is_some()
+                {
+                    continue;
+                }
+
+                if is_nullable(entity, property) {
+                    uses.1 = true;
+                } else {
+                    uses.0 = true;
+                }
+            }
+        }
+    }
+
+    uses
+}
+
+/// Whether properties of the type are deserialized leniently with `lenient_numbers`. Scaled
+/// decimals already accept both numbers and strings.
+fn is_lenient_number(property_type: &PropertyType, opts: &GeneratorOptions) -> bool {
+    match property_type {
+        PropertyType::Decimal { .. } => !scales_decimals(opts),
+        PropertyType::Byte { .. }
+        | PropertyType::Double { .. }
+        | PropertyType::Int16 { .. }
+        | PropertyType::Int32 { .. } => true,
+        _ => false,
+    }
+}
+
+/// Whether nullable properties of the type are deserialized by something other than
+/// `empty_value_as_none`, or left as strings.
+fn has_own_deserializer(property_type: &PropertyType, opts: &GeneratorOptions) -> bool {
+    match property_type {
+        PropertyType::String { .. } => true,
+        PropertyType::Boolean { .. } => opts.lenient_bools,
+        PropertyType::DateTime { .. } => opts.datetime_type.as_deref() == Some("time"),
+        PropertyType::Binary { .. } => derives_deserialize(opts),
+        PropertyType::Decimal { .. } if scales_decimals(opts) => derives_deserialize(opts),
+        _ => opts.lenient_numbers && is_lenient_number(property_type, opts),
+    }
+}
+
+/// Field of a property in an entity struct, annotated with the serde attributes (de)serializing
+/// it from OData.
+fn property_field(
+    namespace: &str,
+    entity: &EntityType,
+    property: &Property,
+    crate_path: &str,
+    vis: &str,
+    rename_rule: Option<&str>,
+    opts: &GeneratorOptions,
+) -> Field {
+    let patchable = opts.triple_state_options && is_nullable(entity, property);
+    let type_override = type_override(namespace, entity, property, opts);
+    // Lenient and custom deserializers expect the bare type rather than the newtype.
+    let custom_de = !patchable && !is_typed_id(entity, property, opts) && type_override.is_none();
+    let typename = if patchable {
+        format!(
+            "{}::Patchable<{}>",
+            crate_path,
+            property_rust_type(namespace, entity, property, opts)
+        )
+    } else {
+        edm_type_to_rust_type(namespace, entity, property, opts)
+    };
+
+    let field_name = property_field_name(entity, property, opts);
+    let mut field = Field::new(&format!("{} {}", vis, field_name), &typename);
+    let mut annotations = Vec::new();
+
+    // Services leave out absent properties rather than sending them as null.
+    if is_nullable(entity, property) && !patchable {
+        annotations.push("#[cfg_attr(feature = \"serde\", serde(default))]".to_string());
+    }
+
+    if opts.skip_none && derives_serialize(opts) && typename.starts_with("Option<") {
+        annotations.push(SKIP_NONE.to_string());
+    }
+
+    if let Some(serde_with) = type_override.and_then(|o| o.serde_with.as_ref()) {
+        annotations.push(format!(
+            "#[cfg_attr(feature = \"serde\", serde(with = \"{}\"))]",
+            serde_with
+        ));
+    }
+
+    if !opts.no_empty_string_is_null && typename == "Option<String>" && type_override.is_none() {
+        annotations.push(format!("#[cfg_attr(feature = \"serde\", serde(deserialize_with = \"{}::empty_string_as_none\"))]", crate_path));
+    };
+
+    let scaled_decimal =
+        scales_decimals(opts) && matches!(property.inner, PropertyType::Decimal { .. });
+
+    if opts.lenient_numbers && custom_de && is_lenient_number(&property.inner, opts) {
+        annotations.push(format!(
+            "#[cfg_attr(feature = \"serde\", serde(deserialize_with = \"{}::{}\"))]",
+            crate_path,
+            if is_nullable(entity, property) {
+                "lenient_optional_number"
+            } else {
+                "lenient_number"
+            }
+        ));
+    }
+
+    // time has no default serde format matching OData, so use RFC 3339 explicitly.
+    if opts.datetime_type.as_deref() == Some("time") && custom_de {
+        if let PropertyType::DateTime { .. } = property.inner {
+            annotations.push(format!(
+                "#[cfg_attr(feature = \"serde\", serde(with = \"{}\"))]",
+                if is_nullable(entity, property) {
+                    "time::serde::rfc3339::option"
+                } else {
+                    "time::serde::rfc3339"
+                }
+            ));
+        }
+    }
+
+    if scaled_decimal && custom_de && serde_derives(opts).is_some() {
+        let optional = if is_nullable(entity, property) {
+            "optional_"
+        } else {
+            ""
+        };
+        let mut with = Vec::new();
+        if derives_serialize(opts) {
+            with.push(format!(
+                "serialize_with = \"{}::serialize_{}scaled_decimal\"",
+                crate_path, optional
+            ));
+        }
+        if derives_deserialize(opts) {
+            with.push(format!(
+                "deserialize_with = \"{}::deserialize_{}scaled_decimal\"",
+                crate_path, optional
+            ));
+        }
+        annotations.push(format!(
+            "#[cfg_attr(feature = \"serde\", serde({}))]",
+            with.join(", ")
+        ));
+    }
+
+    if let (PropertyType::Binary { .. }, true, Some(_)) =
+        (&property.inner, custom_de, serde_derives(opts))
+    {
+        let optional = if is_nullable(entity, property) {
+            "optional_"
+        } else {
+            ""
+        };
+        let mut with = Vec::new();
+        if derives_serialize(opts) {
+            with.push(format!(
+                "serialize_with = \"{}::serialize_{}base64\"",
+                crate_path, optional
+            ));
+        }
+        if derives_deserialize(opts) {
+            with.push(format!(
+                "deserialize_with = \"{}::deserialize_{}base64\"",
+                crate_path, optional
+            ));
+        }
+        annotations.push(format!(
+            "#[cfg_attr(feature = \"serde\", serde({}))]",
+            with.join(", ")
+        ));
+    }
+
+    if opts.lenient_bools && custom_de {
+        if let PropertyType::Boolean { .. } = property.inner {
+            annotations.push(format!(
+                "#[cfg_attr(feature = \"serde\", serde(deserialize_with = \"{}::{}\"))]",
+                crate_path,
+                if is_nullable(entity, property) {
+                    "lenient_optional_bool"
+                } else {
+                    "lenient_bool"
+                }
+            ));
+        }
+    }
+
+    if opts.skip_serializing_keys && derives_serialize(opts) && is_key(entity, property) {
+        annotations.push("#[cfg_attr(feature = \"serde\", serde(skip_serializing))]".to_string());
+    }
+
+    if patchable {
+        annotations.push(format!(
+                "#[cfg_attr(feature = \"serde\", serde(default, skip_serializing_if = \"{}::Patchable::is_missing\"))]",
+                crate_path
+            ));
+    }
+
+    // Only fall back to the generic helper when no other deserializer handles the property.
+    if opts.empty_values_are_null
+        && custom_de
+        && is_nullable(entity, property)
+        && !has_own_deserializer(&property.inner, opts)
+    {
+        annotations.push(format!("#[cfg_attr(feature = \"serde\", serde(deserialize_with = \"{}::empty_value_as_none\"))]", crate_path));
+    }
+
+    let unescaped = field_name.trim_start_matches("r#");
+    let wire_name = rename_rule
+        .map(|rule| apply_rename_rule(rule, unescaped))
+        .unwrap_or_else(|| unescaped.to_string());
+    if property.name != wire_name {
+        annotations.push(format!(
+            "#[cfg_attr(feature = \"serde\", serde(rename = {}))]",
+            string_literal(&property.name)
+        ));
+    }
+    field.annotation(annotations.iter().map(String::as_str).collect());
+
+    field
+}
+
+/// Lines of `validate` checking a property against its MaxLength or Precision facet, if it has
+/// one which can be checked.
+fn property_validation(
+    namespace: &str,
+    entity: &EntityType,
+    property: &Property,
+    crate_path: &str,
+    opts: &GeneratorOptions,
+) -> Option<Vec<String>> {
+    if type_override(namespace, entity, property, opts).is_some() {
+        return None;
+    }
+
+    let field_name = property_field_name(entity, property, opts);
+    // Optional values are bound to `value` by an `if let`, required ones are read in place.
+    let (binding, value) = if opts.triple_state_options && is_nullable(entity, property) {
+        (
+            Some(format!("{}::Patchable::Value(value)", crate_path)),
+            "value".to_string(),
+        )
+    } else if is_nullable(entity, property) {
+        (Some("Some(value)".to_string()), "value".to_string())
+    } else if is_typed_id(entity, property, opts) {
+        (None, format!("self.{}.0", field_name))
+    } else {
+        (None, format!("self.{}", field_name))
+    };
+
+    let (condition, error) = match property.inner {
+        PropertyType::String {
+            max_length: Some(max_length),
+            ..
+        } => (
+            format!("{}.chars().count() > {}", value, max_length),
+            format!(
+                "TooLong {{ property: {}, max_length: {}, length: {}.chars().count() }}",
+                string_literal(&property.name),
+                max_length,
+                value
+            ),
+        ),
+        PropertyType::Binary {
+            max_length: Some(max_length),
+            ..
+        } => (
+            format!("{}.len() > {}", value, max_length),
+            format!(
+                "TooLong {{ property: {}, max_length: {}, length: {}.len() }}",
+                string_literal(&property.name),
+                max_length,
+                value
+            ),
+        ),
+        PropertyType::Decimal {
+            precision: Some(precision),
+            ..
+        } => (
+            match opts.decimal_type.as_deref() {
+                // 10^38 is the largest power of ten an u128 holds.
+                Some("i128") if precision <= 38 => {
+                    format!("{}.unsigned_abs() >= 10u128.pow({})", value, precision)
+                }
+                Some("rust-decimal") if precision <= 38 => format!(
+                    "{}.normalize().mantissa().unsigned_abs() >= 10u128.pow({})",
+                    value, precision
+                ),
+                Some("i128") | Some("rust-decimal") => return None,
+                _ => format!("{}.abs() >= 1e{}", value, precision),
+            },
+            format!(
+                "TooPrecise {{ property: {}, precision: {} }}",
+                string_literal(&property.name),
+                precision
+            ),
+        ),
+        _ => return None,
+    };
+
+    let check = vec![
+        format!("if {} {{", condition),
+        format!("\terrors.push({}::ValidationError::{});", crate_path, error),
+        "}".to_string(),
+    ];
+
+    Some(match binding {
+        Some(binding) => std::iter::once(format!("if let {} = &self.{} {{", binding, field_name))
+            .chain(check.into_iter().map(|line| format!("\t{}", line)))
+            .chain(std::iter::once("}".to_string()))
+            .collect(),
+        None => check,
+    })
+}
+
+/// Lints the generated code is expected to trigger with the given options.
+fn generated_lints(opts: &GeneratorOptions) -> Vec<&'static str> {
+    let mut lints = Vec::new();
+
+    // Items and re-exports which aren't public are reported when the consumer doesn't use them.
+    if opts.visibility.as_deref().unwrap_or("pub") != "pub" {
+        lints.push("dead_code");
+        lints.push("unused_imports");
+    }
+
+    if opts.field_case.as_deref() == Some("original") {
+        lints.push("non_snake_case");
+    }
+
+    if opts.triple_state_options {
+        lints.push("clippy::derivable_impls");
+    }
+
+    if opts.any_entity {
+        lints.push("clippy::large_enum_variant");
+    }
+
+    lints
+}
+
+/// Generates Rust code for all schemas of the metadata document.
+pub fn generate(project: &Edmx, opts: &GeneratorOptions) -> String {
+    generate_with_warnings(project, opts).0
+}
+
+/// Generates Rust code for all schemas of the metadata document, along with warnings about parts
+/// of it which were generated differently than requested, such as navigations whose target
+/// couldn't be resolved.
+pub fn generate_with_warnings(project: &Edmx, opts: &GeneratorOptions) -> (String, Vec<String>) {
+    let vis = opts.visibility.as_deref().unwrap_or("pub");
+    let mut warnings = Vec::new();
+
+    let mut outer = Scope::new();
+
+    // All generated items live in `root`, which is the prefix module if one was requested,
+    // so paths from within the generated code must go through `crate_path` rather than `crate`.
+    let (root, crate_path) = match &opts.module_prefix {
+        Some(prefix) => (
+            outer.new_module(prefix).vis(vis).scope(),
+            format!("crate::{}", prefix),
+        ),
+        None => (&mut outer, "crate".to_string()),
+    };
+    let mut contains_non_ascii = false;
+    let flatten = opts.flatten_single_schema && project.data_services.schemas.len() == 1;
+    let set_names = entity_set_names(project);
+    let original_case = opts.field_case.as_deref() == Some("original");
+
+    // Root helpers are only emitted for the properties using them, as unused ones would be
+    // reported as dead code.
+    let (_, uses_empty_strings) = adapted_properties(project, opts, |property_type| {
+        matches!(property_type, PropertyType::String { .. })
+    });
+
+    if !opts.no_empty_string_is_null && uses_empty_strings {
+        let mut function = Function::new("empty_string_as_none");
+        function.attr("cfg(feature = \"serde\")");
+        function.generic("'de").generic("D").generic("T");
+        function.arg("de", "D");
+        function.ret("Result<Option<T>, D::Error>");
+        function
+            .bound("T", "serde::Deserialize<'de>")
+            .bound("D", "serde::Deserializer<'de>");
+        function.line("let opt: Option<String> = serde::Deserialize::deserialize(de)?;");
+        function.line("let opt = opt.as_deref();");
+        function.line("match opt {");
+        function.line("\tNone | Some(\"\") => Ok(None),");
+        function.line("\tSome(s) => T::deserialize(serde::de::IntoDeserializer::into_deserializer(s)).map(Some),");
+        function.line("}");
+        root.push_fn(function);
+    }
+
+    let (_, uses_empty_values) = adapted_properties(project, opts, |property_type| {
+        !has_own_deserializer(property_type, opts)
+    });
+
+    if opts.empty_values_are_null && uses_empty_values {
+        let empty_value = root.new_enum("EmptyValue").generic("T");
+        empty_value.r#macro("#[cfg(feature = \"serde\")]");
+        empty_value.r#macro("#[derive(Debug, serde::Deserialize)]");
+        empty_value.r#macro("#[serde(untagged)]");
+        empty_value.new_variant("Value").tuple("T");
+        empty_value.new_variant("Str").tuple("String");
+
+        let mut function = Function::new("empty_value_as_none");
+        function.attr("cfg(feature = \"serde\")");
+        function.generic("'de").generic("D").generic("T");
+        function.arg("de", "D");
+        function.ret("Result<Option<T>, D::Error>");
+        function
+            .bound("T", "serde::Deserialize<'de>")
+            .bound("D", "serde::Deserializer<'de>");
+        function.line("let opt: Option<EmptyValue<T>> = serde::Deserialize::deserialize(de)?;");
+        function.line("match opt {");
+        function.line("\tSome(EmptyValue::Value(value)) => Ok(Some(value)),");
+        function.line("\tSome(EmptyValue::Str(s)) if !s.is_empty() => Err(serde::de::Error::custom(format!(\"invalid value {:?}\", s))),");
+        function.line("\t_ => Ok(None),");
+        function.line("}");
+        root.push_fn(function);
+    }
+
+    let (lenient_bool_required, lenient_bool_optional) = if opts.lenient_bools {
+        adapted_properties(project, opts, |property_type| {
+            matches!(property_type, PropertyType::Boolean { .. })
+        })
+    } else {
+        (false, false)
+    };
+
+    if lenient_bool_required || lenient_bool_optional {
+        let lenient_bool = root.new_enum("LenientBool");
+        lenient_bool.r#macro("#[cfg(feature = \"serde\")]");
+        lenient_bool.r#macro("#[derive(Debug, serde::Deserialize)]");
+        lenient_bool.r#macro("#[serde(untagged)]");
+        lenient_bool.new_variant("Bool").tuple("bool");
+        lenient_bool.new_variant("Int").tuple("i64");
+        lenient_bool.new_variant("Str").tuple("String");
+
+        root.new_impl("LenientBool")
+            .r#macro("#[cfg(feature = \"serde\")]")
+            .new_fn("into_bool")
+            .generic("E")
+            .bound("E", "serde::de::Error")
+            .arg_self()
+            .ret("Result<bool, E>")
+            .line("match self {")
+            .line("\tLenientBool::Bool(b) => Ok(b),")
+            .line("\tLenientBool::Int(0) => Ok(false),")
+            .line("\tLenientBool::Int(1) => Ok(true),")
+            .line("\tLenientBool::Str(s) if s.eq_ignore_ascii_case(\"true\") => Ok(true),")
+            .line("\tLenientBool::Str(s) if s.eq_ignore_ascii_case(\"false\") => Ok(false),")
+            .line("\tother => Err(E::custom(format!(\"invalid boolean: {:?}\", other))),")
+            .line("}");
+    }
+
+    if lenient_bool_required {
+        let mut function = Function::new("lenient_bool");
+        function.attr("cfg(feature = \"serde\")");
+        function.generic("'de").generic("D");
+        function.arg("de", "D");
+        function.ret("Result<bool, D::Error>");
+        function.bound("D", "serde::Deserializer<'de>");
+        function.line("<LenientBool as serde::Deserialize>::deserialize(de)?.into_bool()");
+        root.push_fn(function);
+    }
+
+    if lenient_bool_optional {
+        let mut function = Function::new("lenient_optional_bool");
+        function.attr("cfg(feature = \"serde\")");
+        function.generic("'de").generic("D");
+        function.arg("de", "D");
+        function.ret("Result<Option<bool>, D::Error>");
+        function.bound("D", "serde::Deserializer<'de>");
+        function.line("let opt: Option<LenientBool> = serde::Deserialize::deserialize(de)?;");
+        function.line("opt.map(LenientBool::into_bool).transpose()");
+        root.push_fn(function);
+    }
+
+    let (lenient_number_required, lenient_number_optional) = if opts.lenient_numbers {
+        adapted_properties(project, opts, |property_type| {
+            is_lenient_number(property_type, opts)
+        })
+    } else {
+        (false, false)
+    };
+
+    if lenient_number_required || lenient_number_optional {
+        let lenient_number = root.new_enum("LenientNumber").generic("T");
+        lenient_number.r#macro("#[cfg(feature = \"serde\")]");
+        lenient_number.r#macro("#[derive(Debug, serde::Deserialize)]");
+        lenient_number.r#macro("#[serde(untagged)]");
+        lenient_number.new_variant("Number").tuple("T");
+        lenient_number.new_variant("Str").tuple("String");
+
+        root.new_impl("LenientNumber")
+            .r#macro("#[cfg(feature = \"serde\")]")
+            .generic("T")
+            .target_generic("T")
+            .bound("T", "std::str::FromStr")
+            .bound("T::Err", "std::fmt::Display")
+            .new_fn("into_number")
+            .generic("E")
+            .bound("E", "serde::de::Error")
+            .arg_self()
+            .ret("Result<T, E>")
+            .line("match self {")
+            .line("\tLenientNumber::Number(n) => Ok(n),")
+            .line("\tLenientNumber::Str(s) => s.trim().parse().map_err(|e| E::custom(format!(\"invalid number {:?}: {}\", s, e))),")
+            .line("}");
+    }
+
+    if lenient_number_required {
+        let mut function = Function::new("lenient_number");
+        function.attr("cfg(feature = \"serde\")");
+        function.generic("'de").generic("D").generic("T");
+        function.arg("de", "D");
+        function.ret("Result<T, D::Error>");
+        function.bound("D", "serde::Deserializer<'de>");
+        function.bound("T", "serde::Deserialize<'de> + std::str::FromStr");
+        function.bound("T::Err", "std::fmt::Display");
+        function.line("<LenientNumber<T> as serde::Deserialize>::deserialize(de)?.into_number()");
+        root.push_fn(function);
+    }
+
+    if lenient_number_optional {
+        let mut function = Function::new("lenient_optional_number");
+        function.attr("cfg(feature = \"serde\")");
+        function.generic("'de").generic("D").generic("T");
+        function.arg("de", "D");
+        function.ret("Result<Option<T>, D::Error>");
+        function.bound("D", "serde::Deserializer<'de>");
+        function.bound("T", "serde::Deserialize<'de> + std::str::FromStr");
+        function.bound("T::Err", "std::fmt::Display");
+        function.line("let opt: Option<LenientNumber<T>> = serde::Deserialize::deserialize(de)?;");
+        function.line("opt.map(LenientNumber::into_number).transpose()");
+        root.push_fn(function);
+    }
+
+    // OData sends binary properties as base64 strings rather than arrays of numbers.
+    let (base64_required, base64_optional) = adapted_properties(project, opts, |property_type| {
+        matches!(property_type, PropertyType::Binary { .. })
+    });
+    let uses_binary = base64_required || base64_optional;
+
+    if uses_binary && derives_serialize(opts) {
+        let mut function = Function::new("base64_encode");
+        function.attr("cfg(feature = \"serde\")");
+        function.arg("bytes", "&[u8]");
+        function.ret("String");
+        function.line("let mut text = String::with_capacity(bytes.len() * 4 / 3 + 4);");
+        function.line("for chunk in bytes.chunks(3) {");
+        function.line("\tlet buffer = chunk.iter().enumerate().fold(0u32, |buffer, (i, byte)| buffer | (u32::from(*byte) << (16 - 8 * i)));");
+        function.line("\tfor i in 0..4 {");
+        function.line("\t\tif i <= chunk.len() {");
+        function.line(
+            "\t\t\ttext.push(BASE64_ALPHABET[((buffer >> (18 - 6 * i)) & 0x3f) as usize] as char);",
+        );
+        function.line("\t\t} else {");
+        function.line("\t\t\ttext.push('=');");
+        function.line("\t\t}");
+        function.line("\t}");
+        function.line("}");
+        function.line("text");
+        root.push_fn(function);
+
+        if base64_required {
+            let mut function = Function::new("serialize_base64");
+            function.attr("cfg(feature = \"serde\")");
+            function.generic("S");
+            function.arg("bytes", "&[u8]");
+            function.arg("serializer", "S");
+            function.ret("Result<S::Ok, S::Error>");
+            function.bound("S", "serde::Serializer");
+            function.line("serializer.serialize_str(&base64_encode(bytes))");
+            root.push_fn(function);
+        }
+
+        if base64_optional {
+            let mut function = Function::new("serialize_optional_base64");
+            function.attr("cfg(feature = \"serde\")");
+            function.generic("S");
+            function.arg("bytes", "&Option<Vec<u8>>");
+            function.arg("serializer", "S");
+            function.ret("Result<S::Ok, S::Error>");
+            function.bound("S", "serde::Serializer");
+            function.line("match bytes {");
+            function.line("\tSome(bytes) => serializer.serialize_some(&base64_encode(bytes)),");
+            function.line("\tNone => serializer.serialize_none(),");
+            function.line("}");
+            root.push_fn(function);
+        }
+    }
+
+    if uses_binary && derives_deserialize(opts) {
+        // Both the standard and the URL-safe alphabet are accepted, as OData v4 uses the latter.
+        let mut function = Function::new("base64_decode");
+        function.attr("cfg(feature = \"serde\")");
+        function.arg("text", "&str");
+        function.ret("Option<Vec<u8>>");
+        function.line("let mut bytes = Vec::with_capacity(text.len() / 4 * 3);");
+        function.line("let (mut buffer, mut bits) = (0u32, 0);");
+        function.line("for c in text.bytes().filter(|c| *c != b'=' && !c.is_ascii_whitespace()) {");
+        function.line("\tlet value = match c {");
+        function.line("\t\tb'-' => 62,");
+        function.line("\t\tb'_' => 63,");
+        function.line("\t\t_ => BASE64_ALPHABET.iter().position(|a| *a == c)? as u32,");
+        function.line("\t};");
+        function.line("\tbuffer = (buffer << 6) | value;");
+        function.line("\tbits += 6;");
+        function.line("\tif bits >= 8 {");
+        function.line("\t\tbits -= 8;");
+        function.line("\t\tbytes.push((buffer >> bits) as u8);");
+        function.line("\t}");
+        function.line("}");
+        // A single character past the last full group can't hold a whole byte.
+        function.line("if bits == 6 {");
+        function.line("\treturn None;");
+        function.line("}");
+        function.line("Some(bytes)");
+        root.push_fn(function);
+
+        if base64_required {
+            let mut function = Function::new("deserialize_base64");
+            function.attr("cfg(feature = \"serde\")");
+            function.generic("'de").generic("D");
+            function.arg("de", "D");
+            function.ret("Result<Vec<u8>, D::Error>");
+            function.bound("D", "serde::Deserializer<'de>");
+            function.line("let text: String = serde::Deserialize::deserialize(de)?;");
+            function.line("base64_decode(&text).ok_or_else(|| serde::de::Error::custom(format!(\"invalid base64 {:?}\", text)))");
+            root.push_fn(function);
+        }
+
+        if base64_optional {
+            let mut function = Function::new("deserialize_optional_base64");
+            function.attr("cfg(feature = \"serde\")");
+            function.generic("'de").generic("D");
+            function.arg("de", "D");
+            function.ret("Result<Option<Vec<u8>>, D::Error>");
+            function.bound("D", "serde::Deserializer<'de>");
+            function.line("let opt: Option<String> = serde::Deserialize::deserialize(de)?;");
+            function.line("opt.map(|text| base64_decode(&text).ok_or_else(|| serde::de::Error::custom(format!(\"invalid base64 {:?}\", text)))).transpose()");
+            root.push_fn(function);
+        }
+    }
+
+    if uses_binary && !opts.no_serde {
+        root.raw("#[cfg(feature = \"serde\")]\nconst BASE64_ALPHABET: &[u8; 64] = b\"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/\";");
+    }
+
+    // Scaled decimals are sent as strings, which keeps digits f64 couldn't represent.
+    let (decimal_required, decimal_optional) = if scales_decimals(opts) {
+        adapted_properties(project, opts, |property_type| {
+            matches!(property_type, PropertyType::Decimal { .. })
+        })
+    } else {
+        (false, false)
+    };
+    let uses_scaled_decimals = decimal_required || decimal_optional;
+
+    if uses_scaled_decimals && !opts.no_serde {
+        root.raw(&format!(
+            "#[cfg(feature = \"serde\")]\nconst DECIMAL_SCALE: usize = {};",
+            opts.decimal_scale.unwrap_or(0)
+        ));
+    }
+
+    if uses_scaled_decimals && derives_serialize(opts) {
+        let mut function = Function::new("format_scaled_decimal");
+        function.attr("cfg(feature = \"serde\")");
+        function.arg("value", "i128");
+        function.ret("String");
+        function.line("let digits = format!(\"{:0width$}\", value.unsigned_abs(), width = DECIMAL_SCALE + 1);");
+        function.line("let (integer, fraction) = digits.split_at(digits.len() - DECIMAL_SCALE);");
+        function.line("let sign = if value < 0 { \"-\" } else { \"\" };");
+        function.line("if fraction.is_empty() {");
+        function.line("\tformat!(\"{}{}\", sign, integer)");
+        function.line("} else {");
+        function.line("\tformat!(\"{}{}.{}\", sign, integer, fraction)");
+        function.line("}");
+        root.push_fn(function);
+
+        if decimal_required {
+            let mut function = Function::new("serialize_scaled_decimal");
+            function.attr("cfg(feature = \"serde\")");
+            function.generic("S");
+            function.arg("value", "&i128");
+            function.arg("serializer", "S");
+            function.ret("Result<S::Ok, S::Error>");
+            function.bound("S", "serde::Serializer");
+            function.line("serializer.serialize_str(&format_scaled_decimal(*value))");
+            root.push_fn(function);
+        }
+
+        if decimal_optional {
+            let mut function = Function::new("serialize_optional_scaled_decimal");
+            function.attr("cfg(feature = \"serde\")");
+            function.generic("S");
+            function.arg("value", "&Option<i128>");
+            function.arg("serializer", "S");
+            function.ret("Result<S::Ok, S::Error>");
+            function.bound("S", "serde::Serializer");
+            function.line("match value {");
+            function.line(
+                "\tSome(value) => serializer.serialize_some(&format_scaled_decimal(*value)),",
+            );
+            function.line("\tNone => serializer.serialize_none(),");
+            function.line("}");
+            root.push_fn(function);
+        }
+    }
+
+    if uses_scaled_decimals && derives_deserialize(opts) {
+        // Digits beyond the scale are rounded half away from zero.
+        let mut function = Function::new("parse_scaled_decimal");
+        function.attr("cfg(feature = \"serde\")");
+        function.arg("text", "&str");
+        function.ret("Option<i128>");
+        function.line("let text = text.trim();");
+        function.line("let (sign, unsigned) = match text.strip_prefix('-') {");
+        function.line("\tSome(unsigned) => (-1, unsigned),");
+        function.line("\tNone => (1, text.strip_prefix('+').unwrap_or(text)),");
+        function.line("};");
+        function.line("let (mantissa, exponent) = match unsigned.find(['e', 'E']) {");
+        function.line("\tSome(i) => (&unsigned[..i], unsigned[i + 1..].parse::<i32>().ok()?),");
+        function.line("\tNone => (unsigned, 0),");
+        function.line("};");
+        function.line(
+            "let (integer, fraction) = mantissa.split_once('.').unwrap_or((mantissa, \"\"));",
+        );
+        function.line("if (integer.is_empty() && fraction.is_empty()) || !integer.bytes().chain(fraction.bytes()).all(|c| c.is_ascii_digit()) {");
+        function.line("\treturn None;");
+        function.line("}");
+        function.line("let digits: Vec<u8> = integer.bytes().chain(fraction.bytes()).map(|c| c - b'0').collect();");
+        // Where the decimal point of the scaled value falls among the digits. A point before the
+        // first digit leaves nothing to round up, and bounding it makes huge exponents overflow
+        // below instead of looping over zeros. Accumulating with the sign applied lets i128::MIN
+        // through.
+        function
+            .line("let point = integer.len() as i64 + i64::from(exponent) + DECIMAL_SCALE as i64;");
+        function.line("if point < 0 {");
+        function.line("\treturn Some(0);");
+        function.line("}");
+        function.line("let point = point.min(digits.len() as i64 + 64) as usize;");
+        function.line("let mut value = 0i128;");
+        function.line("for i in 0..point {");
+        function.line("\tvalue = value.checked_mul(10)?.checked_add(sign * i128::from(digits.get(i).copied().unwrap_or(0)))?;");
+        function.line("}");
+        function.line("if matches!(digits.get(point), Some(digit) if *digit >= 5) {");
+        function.line("\tvalue = value.checked_add(sign)?;");
+        function.line("}");
+        function.line("Some(value)");
+        root.push_fn(function);
+
+        // OData v2 sends decimals as strings, while v4 services may send plain numbers.
+        let decimal_text = root.new_enum("DecimalText");
+        decimal_text.r#macro("#[cfg(feature = \"serde\")]");
+        decimal_text.r#macro("#[derive(Debug, serde::Deserialize)]");
+        decimal_text.r#macro("#[serde(untagged)]");
+        decimal_text.new_variant("Str").tuple("String");
+        decimal_text.new_variant("Integer").tuple("i64");
+        decimal_text.new_variant("Float").tuple("f64");
+
+        root.new_impl("DecimalText")
+            .r#macro("#[cfg(feature = \"serde\")]")
+            .new_fn("into_scaled")
+            .generic("E")
+            .bound("E", "serde::de::Error")
+            .arg_self()
+            .ret("Result<i128, E>")
+            .line("let text = match self {")
+            .line("\tDecimalText::Str(text) => text,")
+            .line("\tDecimalText::Integer(n) => n.to_string(),")
+            .line("\tDecimalText::Float(n) => n.to_string(),")
+            .line("};")
+            .line("parse_scaled_decimal(&text).ok_or_else(|| E::custom(format!(\"invalid decimal {:?}\", text)))");
+
+        if decimal_required {
+            let mut function = Function::new("deserialize_scaled_decimal");
+            function.attr("cfg(feature = \"serde\")");
+            function.generic("'de").generic("D");
+            function.arg("de", "D");
+            function.ret("Result<i128, D::Error>");
+            function.bound("D", "serde::Deserializer<'de>");
+            function.line("<DecimalText as serde::Deserialize>::deserialize(de)?.into_scaled()");
+            root.push_fn(function);
+        }
+
+        if decimal_optional {
+            let mut function = Function::new("deserialize_optional_scaled_decimal");
+            function.attr("cfg(feature = \"serde\")");
+            function.generic("'de").generic("D");
+            function.arg("de", "D");
+            function.ret("Result<Option<i128>, D::Error>");
+            function.bound("D", "serde::Deserializer<'de>");
+            function.line("let opt: Option<DecimalText> = serde::Deserialize::deserialize(de)?;");
+            function.line("opt.map(DecimalText::into_scaled).transpose()");
+            root.push_fn(function);
+        }
+    }
+
+    if opts.validate {
+        let error = root.new_enum("ValidationError").vis(vis);
+        error.doc(
+            "A property value which doesn't fit the facets declared in the metadata document.",
+        );
+        error
+            .derive("Debug")
+            .derive("Clone")
+            .derive("PartialEq")
+            .derive("Eq");
+        error
+            .new_variant("TooLong")
+            .named("property", "&'static str")
+            .named("max_length", "usize")
+            .named("length", "usize");
+        error
+            .new_variant("TooPrecise")
+            .named("property", "&'static str")
+            .named("precision", "u8");
+
+        root.new_impl("ValidationError")
+            .impl_trait("std::fmt::Display")
+            .new_fn("fmt")
+            .arg_ref_self()
+            .arg("f", "&mut std::fmt::Formatter<'_>")
+            .ret("std::fmt::Result")
+            .line("match self {")
+            .line("\tValidationError::TooLong { property, max_length, length } => write!(f, \"{} is {} long, exceeding its MaxLength of {}\", property, length, max_length),")
+            .line("\tValidationError::TooPrecise { property, precision } => write!(f, \"{} has more digits than its Precision of {}\", property, precision),")
+            .line("}");
+
+        root.new_impl("ValidationError")
+            .impl_trait("std::error::Error");
+    }
+
+    if opts.triple_state_options {
+        let patchable = root.new_enum("Patchable").vis(vis).generic("T");
+        patchable.doc("A nullable value which also tracks whether it was present at all.");
+        patchable
+            .derive("Debug")
+            .derive("Clone")
+            .derive("PartialEq");
+        patchable.new_variant("Missing");
+        patchable.new_variant("Null");
+        patchable.new_variant("Value").tuple("T");
+
+        root.new_impl("Patchable")
+            .generic("T")
+            .target_generic("T")
+            .impl_trait("Default")
+            .new_fn("default")
+            .ret("Self")
+            .line("Patchable::Missing");
+
+        root.new_impl("Patchable")
+            .generic("T")
+            .target_generic("T")
+            .new_fn("is_missing")
+            .vis(vis)
+            .arg_ref_self()
+            .ret("bool")
+            .line("matches!(self, Patchable::Missing)");
+
+        root.new_impl("Patchable")
+            .r#macro("#[cfg(feature = \"serde\")]")
+            .generic("T")
+            .target_generic("T")
+            .bound("T", "serde::Serialize")
+            .impl_trait("serde::Serialize")
+            .new_fn("serialize")
+            .generic("S")
+            .bound("S", "serde::Serializer")
+            .arg_ref_self()
+            .arg("serializer", "S")
+            .ret("Result<S::Ok, S::Error>")
+            .line("match self {")
+            .line("\tPatchable::Value(value) => serializer.serialize_some(value),")
+            .line("\t_ => serializer.serialize_none(),")
+            .line("}");
+
+        root.new_impl("Patchable")
+            .r#macro("#[cfg(feature = \"serde\")]")
+            .generic("'de")
+            .generic("T")
+            .target_generic("T")
+            .bound("T", "serde::Deserialize<'de>")
+            .impl_trait("serde::Deserialize<'de>")
+            .new_fn("deserialize")
+            .generic("D")
+            .bound("D", "serde::Deserializer<'de>")
+            .arg("de", "D")
+            .ret("Result<Self, D::Error>")
+            .line("let opt: Option<T> = serde::Deserialize::deserialize(de)?;")
+            .line("Ok(opt.map_or(Patchable::Null, Patchable::Value))");
+    }
+
+    if opts.envelope || opts.decode_collections {
+        let response = root.new_struct("ODataResponse").vis(vis).generic("T");
+        response.doc("Collection response of an OData v4 service.");
+        response.r#macro("#[derive(Debug)]");
+        if let Some(derives) = serde_derives(opts) {
+            response.r#macro(&derives);
+        }
+        response.field(&format!("{} value", vis), "Vec<T>");
+        let mut next_link = Field::new(&format!("{} next_link", vis), "Option<String>");
+        next_link.annotation(vec![
+            "#[cfg_attr(feature = \"serde\", serde(rename = \"@odata.nextLink\", default))]",
+        ]);
+        response.push_field(next_link);
+
+        let response = root.new_struct("ODataV2Response").vis(vis).generic("T");
+        response.doc("Collection response of an OData v2 service, wrapped in its \"d\" object.");
+        response.r#macro("#[derive(Debug)]");
+        if let Some(derives) = serde_derives(opts) {
+            response.r#macro(&derives);
+        }
+        response.field(&format!("{} d", vis), "ODataV2Results<T>");
+
+        let results = root.new_struct("ODataV2Results").vis(vis).generic("T");
+        results.r#macro("#[derive(Debug)]");
+        if let Some(derives) = serde_derives(opts) {
+            results.r#macro(&derives);
+        }
+        results.field(&format!("{} results", vis), "Vec<T>");
+        let mut next_link = Field::new(&format!("{} next_link", vis), "Option<String>");
+        next_link.annotation(vec![
+            "#[cfg_attr(feature = \"serde\", serde(rename = \"__next\", default))]",
+        ]);
+        results.push_field(next_link);
+
+        root.new_impl("ODataResponse")
+            .generic("T")
+            .target_generic("T")
+            .impl_trait("From<ODataV2Response<T>>")
+            .new_fn("from")
+            .arg("response", "ODataV2Response<T>")
+            .ret("Self")
+            .line("ODataResponse {")
+            .line("\tvalue: response.d.results,")
+            .line("\tnext_link: response.d.next_link,")
+            .line("}");
+    }
+
+    if !set_names.is_empty() {
+        root.raw(&format!(
+            "/// Links an entity type to the name of the entity set containing it. Entity types\n\
+             /// contained in several entity sets are linked to the first of them.\n\
+             {} trait EntitySetName {{\n    const SET_NAME: &'static str;\n}}",
+            vis
+        ));
+    }
+
+    if opts.entity_set_markers && !set_names.is_empty() {
+        root.raw(&format!(
+            "/// An entity set of the service, linking the path it's found at to the type of its\n\
+             /// entities and their keys.\n\
+             {} trait EntitySet {{\n    type Entity;\n    type Key;\n    const PATH: &'static str;\n}}",
+            vis
+        ));
+    }
+
+    let has_entities = project
+        .data_services
+        .schemas
+        .iter()
+        .any(|schema| !schema.entities.is_empty());
+
+    if !opts.no_reflection {
+        let mut opendata_model = Trait::new("OpenDataModel");
+        opendata_model.r#macro("#[cfg(feature = \"reflection\")]");
+        opendata_model.vis(vis);
+        opendata_model.new_fn("name").ret("&'static str");
+        opendata_model
+            .new_fn("entity_set")
+            .ret("Option<&'static str>");
+        opendata_model
+            .new_fn("fields")
+            .ret("&'static [(&'static str, OpenDataType)]");
+        opendata_model
+            .new_fn("relations")
+            .ret("&'static [(&'static str, &'static str, &'static str)]");
+        opendata_model
+            .new_fn("get_field")
+            .arg_ref_self()
+            .arg("name", "&str")
+            .ret("Option<OpenDataValue<'_>>");
+
+        if has_entities {
+            opendata_model
+                .new_fn("expandable_paths")
+                .doc("Paths which can be $expand-ed from the entity, following navigations up to `depth` levels deep.")
+                .arg("depth", "usize")
+                .ret("Vec<String>")
+                .line("expand_paths(Self::relations(), depth)");
+        }
+
+        if opts.to_json && derives_serialize(opts) {
+            opendata_model
+                .new_fn("to_json")
+                .doc("Serializes the entity into a JSON value keyed by the OData property names.")
+                .attr("cfg(feature = \"serde\")")
+                .arg_ref_self()
+                .ret("Result<serde_json::Value, serde_json::Error>")
+                .bound("Self", "serde::Serialize")
+                .line("serde_json::to_value(self)");
+        }
+        root.push_trait(opendata_model);
+
+        let datatype = root.new_enum("OpenDataType").vis(vis);
+        datatype.doc(
+            "Type of a property, along with the facets declared for it in the metadata document. \
+             odata-parser-rs doesn't read MaxLength, FixedLength or Precision yet, so `max_length`, `fixed_length` and `precision` are `None` for parsed documents.",
+        );
+        datatype.r#macro("#[cfg(feature = \"reflection\")]");
+
+        for prototype in property_type_prototypes() {
+            let mapping = type_mapping(&prototype);
+            let variant = datatype
+                .new_variant(mapping.variant)
+                .named("nullable", "bool")
+                .named("key", "bool");
+
+            for (name, ty, _) in mapping.facets {
+                variant.named(name, ty);
+            }
+        }
+
+        let edm_name = root
+            .new_impl("OpenDataType")
+            .r#macro("#[cfg(feature = \"reflection\")]")
+            .new_fn("edm_name")
+            .doc("Name of the type in the metadata document, such as `Edm.Int32`.")
+            .vis(vis)
+            .arg_ref_self()
+            .ret("&'static str")
+            .line("match self {");
+        for prototype in property_type_prototypes() {
+            let variant = type_mapping(&prototype).variant;
+            edm_name.line(format!(
+                "\tOpenDataType::{} {{ .. }} => \"Edm.{}\",",
+                variant, variant
+            ));
+        }
+        edm_name.line("}");
+
+        let value = root.new_enum("OpenDataValue").vis(vis).generic("'a");
+        value.doc("Value of a property, as returned by `OpenDataModel::get_field`.");
+        value.r#macro("#[cfg(feature = \"reflection\")]");
+        value.derive("Debug").derive("PartialEq");
+        value.new_variant("Null");
+
+        for prototype in property_type_prototypes() {
+            let mapping = type_mapping(&prototype);
+            value
+                .new_variant(mapping.variant)
+                .tuple(&format!("&'a {}", rust_type(&prototype, opts)));
+        }
+    }
+
+    let mut default_exports = Vec::new();
+    let mut prelude = Vec::new();
+    let mut macro_names = HashSet::new();
+    for schema in ordered(
+        &project.data_services.schemas,
+        |schema| &schema.namespace,
+        opts,
+    ) {
+        let strip_prefix = opts.strip_prefix.as_deref();
+        let schema_path = module_path(&crate_path, &schema.namespace, strip_prefix, flatten);
+        let head = if flatten {
+            &mut *root
+        } else {
+            let mut path_segments = module_segments(&schema.namespace, strip_prefix);
+            let mut module = root.get_or_new_module(&path_segments.pop_front().unwrap());
+            module.vis(vis);
+
+            for path_segment in path_segments {
+                module = module.get_or_new_module(&path_segment);
+                module.vis(vis);
+                contains_non_ascii = contains_non_ascii || path_segment.is_ascii();
+            }
+            module.scope()
+        };
+
+        if !opts.no_reflection && !schema.entities.is_empty() {
+            let entity_types = head
+                .new_fn("entity_types")
+                .attr("cfg(feature = \"reflection\")")
+                .vis(vis)
+                .ret(format!(
+                    "&'static [(&'static str, &'static [(&'static str, {}::OpenDataType)])]",
+                    crate_path
+                ))
+                .line("&[");
+
+            for entity in ordered(&schema.entities, |entity| &entity.name, opts) {
+                entity_types.line(format!(
+                    "\t(\"{}\", {}),",
+                    entity.name,
+                    entity_type_reflection(entity, &crate_path, opts)
+                ));
+            }
+            entity_types.line("]");
+        }
+
+        for entity in ordered(&schema.entities, |entity| &entity.name, opts) {
+            let rename_rule = if opts.rename_all && !opts.no_serde {
+                detect_rename_rule(entity, opts)
+            } else {
+                None
+            };
+            let wire_name = |field: &str| {
+                rename_rule
+                    .map(|rule| apply_rename_rule(rule, field))
+                    .unwrap_or_else(|| field.to_string())
+            };
+
+            let qualified_name = format!("{}.{}", schema.namespace, entity.name);
+            prelude.push((schema_path.clone(), entity.name.clone()));
+
+            if opts.typed_ids {
+                if let Some(key) = entity.key_property() {
+                    let id_name = format!("{}Id", entity.name);
+                    prelude.push((schema_path.clone(), id_name.clone()));
+                    let inner = rust_type(&key.inner, opts);
+
+                    let mut derives = vec!["Debug", "Clone"];
+                    if is_copy(key) {
+                        derives.push("Copy");
+                    }
+                    derives.push("PartialEq");
+                    if is_hashable(key) {
+                        derives.extend(["Eq", "Hash"]);
+                    }
+
+                    let id = head.new_struct(&id_name);
+                    id.vis(vis)
+                        .doc(&format!("Key of {} entities.", entity.name))
+                        .r#macro(&format!("#[derive({})]", derives.join(", ")));
+                    if opts.emit_tests && round_trips(&schema.namespace, entity, opts) {
+                        id.r#macro("#[cfg_attr(test, derive(Default))]");
+                    }
+                    if let Some(derives) = serde_derives(opts) {
+                        id.r#macro(&derives)
+                            .r#macro("#[cfg_attr(feature = \"serde\", serde(transparent))]");
+                    }
+                    id.tuple_field(format!("{} {}", vis, inner));
+
+                    head.new_impl(&id_name)
+                        .impl_trait("std::ops::Deref")
+                        .associate_type("Target", inner)
+                        .new_fn("deref")
+                        .arg_ref_self()
+                        .ret("&Self::Target")
+                        .line("&self.0");
+
+                    if let PropertyType::String { .. } = key.inner {
+                        // Hashing matches str, so maps keyed by ids can be queried with a &str.
+                        head.new_impl(&id_name)
+                            .impl_trait("AsRef<str>")
+                            .new_fn("as_ref")
+                            .arg_ref_self()
+                            .ret("&str")
+                            .line("&self.0");
+
+                        head.new_impl(&id_name)
+                            .impl_trait("std::borrow::Borrow<str>")
+                            .new_fn("borrow")
+                            .arg_ref_self()
+                            .ret("&str")
+                            .line("&self.0");
+                    }
+
+                    head.new_impl(&id_name)
+                        .impl_trait(format!("From<{}>", inner))
+                        .new_fn("from")
+                        .arg("id", inner)
+                        .ret("Self")
+                        .line(format!("{}(id)", id_name));
+                }
+            }
+
+            let obj = head.new_struct(&entity.name);
+            obj.vis(vis);
+
+            if original_case {
+                obj.allow("non_snake_case");
+            }
+
+            if opts.namespace_docs {
+                obj.doc(&match set_names.get(qualified_name.as_str()) {
+                    Some(set) => format!("OData entity {} (set: {})", qualified_name, set),
+                    None => format!("OData entity {}", qualified_name),
+                });
+            }
+            obj.r#macro("#[derive(Debug)]");
+
+            // The round trip tests compare entities, including any they navigate to.
+            if opts.emit_tests {
+                let mut test_derives = Vec::new();
+                if round_trips(&schema.namespace, entity, opts) {
+                    test_derives.push("Default");
+                }
+                if !opts.eq_ignores_navigations {
+                    test_derives.push("PartialEq");
+                }
+                if !test_derives.is_empty() {
+                    obj.r#macro(&format!(
+                        "#[cfg_attr(test, derive({}))]",
+                        test_derives.join(", ")
+                    ));
+                }
+            }
+
+            if opts.non_exhaustive {
+                obj.r#macro("#[non_exhaustive]");
+            }
+
+            if let Some(derives) = serde_derives(opts) {
+                obj.r#macro(&derives);
+            }
+
+            if let Some(rule) = rename_rule {
+                obj.r#macro(&format!(
+                    "#[cfg_attr(feature = \"serde\", serde(rename_all = \"{}\"))]",
+                    rule
+                ));
+            }
+
+            for property in &entity.properties {
+                obj.push_field(property_field(
+                    &schema.namespace,
+                    entity,
+                    property,
+                    &crate_path,
+                    vis,
+                    rename_rule,
+                    opts,
+                ));
+            }
+
+            let mut navigation_fields = Vec::new();
+            let mut accessors = Vec::new();
+            if !opts.no_expand {
+                for navigation_property in &entity.navigations {
+                    let field_name = navigation_field_name(entity, navigation_property, opts);
+                    let (typename, doc, required) = match lookup_entity_type(
+                        project,
+                        schema,
+                        navigation_property,
+                    ) {
+                        Some((qualified_target, multiplicity)) => {
+                            let path = entity_type_path(
+                                &crate_path,
+                                &qualified_target,
+                                strip_prefix,
+                                flatten,
+                            );
+                            // Targets within the same schema are referred to by name.
+                            let target = match qualified_target.rsplit_once('.') {
+                                Some((namespace, name)) if namespace == schema.namespace => {
+                                    name.to_string()
+                                }
+                                _ => path.clone(),
+                            };
+
+                            let pointer = shared_nav_ptr(opts).unwrap_or("Box");
+                            let (typename, accessor) = match multiplicity.as_str() {
+                                "1" if opts.required_navigations => (
+                                    format!("{}<{}>", pointer, target),
+                                    (format!("&{}", target), format!("&self.{}", field_name)),
+                                ),
+                                // Required ends are still only present when $expand-ed.
+                                "0..1" | "1" => (
+                                    format!("Option<{}<{}>>", pointer, target),
+                                    (
+                                        format!("Option<&{}>", target),
+                                        format!("self.{}.as_deref()", field_name),
+                                    ),
+                                ),
+                                _ => match shared_nav_ptr(opts) {
+                                    Some(pointer) => (
+                                        format!("Vec<{}<{}>>", pointer, target),
+                                        (
+                                            format!("impl Iterator<Item = &{}> + '_", target),
+                                            format!(
+                                                "self.{}.iter().map(std::ops::Deref::deref)",
+                                                field_name
+                                            ),
+                                        ),
+                                    ),
+                                    None => (
+                                        format!("Vec<{}>", target),
+                                        (
+                                            format!("impl Iterator<Item = &{}> + '_", target),
+                                            format!("self.{}.iter()", field_name),
+                                        ),
+                                    ),
+                                },
+                            };
+                            accessors.push((field_name.clone(), accessor));
+                            let required = multiplicity == "1" && opts.required_navigations;
+                            (typename, format!("See [`{}`]", path), required)
+                        }
+                        None => {
+                            warnings.push(format!(
+                                "could not resolve the target of navigation {}.{} through {}, generating it as serde_json::Value",
+                                qualified_name, navigation_property.name, navigation_property.relationship
+                            ));
+                            (
+                                "serde_json::Value".to_string(),
+                                format!(
+                                    "Unresolved navigation through {}",
+                                    navigation_property.relationship
+                                ),
+                                false,
+                            )
+                        }
+                    };
+
+                    let mut field = Field::new(&format!("{} {}", vis, field_name), &typename);
+                    field.doc(vec![&doc]);
+
+                    let mut annotations = Vec::new();
+                    if navigation_property.name != wire_name(field_name.trim_start_matches("r#")) {
+                        annotations.push(format!(
+                            "#[cfg_attr(feature = \"serde\", serde(rename = {}{}))]",
+                            string_literal(&navigation_property.name),
+                            if required { "" } else { ", default" }
+                        ));
+                    } else if !required {
+                        // Services only include navigations when they're $expand-ed.
+                        annotations
+                            .push("#[cfg_attr(feature = \"serde\", serde(default))]".to_string());
+                    }
+
+                    if opts.skip_none && derives_serialize(opts) && typename.starts_with("Option<")
+                    {
+                        annotations.push(SKIP_NONE.to_string());
+                    }
+                    field.annotation(annotations.iter().map(String::as_str).collect());
+
+                    navigation_fields.push(field);
+                }
+            }
+
+            let owner = if opts.group_navigations {
+                format!("{}Expanded", entity.name)
+            } else {
+                entity.name.clone()
+            };
+
+            if !opts.group_navigations {
+                for field in navigation_fields {
+                    obj.push_field(field);
+                }
+            } else if !navigation_fields.is_empty() {
+                let expanded_name = format!("{}Expanded", entity.name);
+                let expanded = head.new_struct(&expanded_name);
+                expanded.vis(vis);
+
+                if original_case {
+                    expanded.allow("non_snake_case");
+                }
+                expanded.r#macro("#[derive(Debug)]");
+
+                if opts.non_exhaustive {
+                    expanded.r#macro("#[non_exhaustive]");
+                }
+
+                if let Some(derives) = serde_derives(opts) {
+                    expanded.r#macro(&derives);
+                }
+
+                if let Some(rule) = rename_rule {
+                    expanded.r#macro(&format!(
+                        "#[cfg_attr(feature = \"serde\", serde(rename_all = \"{}\"))]",
+                        rule
+                    ));
+                }
+
+                let mut base = Field::new(&format!("{} base", vis), &entity.name);
+                base.annotation(vec!["#[cfg_attr(feature = \"serde\", serde(flatten))]"]);
+                expanded.push_field(base);
+
+                for field in navigation_fields {
+                    expanded.push_field(field);
+                }
+
+                head.new_impl(&expanded_name)
+                    .impl_trait("std::ops::Deref")
+                    .associate_type("Target", &entity.name)
+                    .new_fn("deref")
+                    .arg_ref_self()
+                    .ret("&Self::Target")
+                    .line("&self.base");
+
+                head.new_impl(&entity.name)
+                    .impl_trait(format!("From<{}>", expanded_name))
+                    .new_fn("from")
+                    .arg("expanded", &expanded_name)
+                    .ret("Self")
+                    .line("expanded.base");
+            }
+
+            if opts.nav_accessors && !accessors.is_empty() {
+                let impl_block = head.new_impl(&owner);
+                if original_case {
+                    impl_block.r#macro("#[allow(non_snake_case)]");
+                }
+
+                for (name, (ret, body)) in accessors {
+                    impl_block
+                        .new_fn(&name)
+                        .vis(vis)
+                        .arg_ref_self()
+                        .ret(ret)
+                        .line(body);
+                }
+            }
+
+            if !opts.no_reflection {
+                let fields = entity_type_reflection(entity, &crate_path, opts);
+                let expansions = ordered(&entity.navigations, |nav| &nav.name, opts)
+                    .into_iter()
+                    .filter_map(|nav| {
+                        let (target, multiplicity) = lookup_entity_type(project, schema, nav)?;
+                        Some(format!(
+                            "({}, \"{}\", \"{}\")",
+                            string_literal(&nav.name),
+                            target
+                                .rsplit_once('.')
+                                .map_or(target.as_str(), |(_, name)| name),
+                            cardinality(&multiplicity)
+                        ))
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+
+                let opendata_model = head
+                    .new_impl(&entity.name)
+                    .impl_trait(format!("{}::OpenDataModel", crate_path));
+                opendata_model.r#macro("#[cfg(feature = \"reflection\")]");
+                opendata_model
+                    .new_fn("name")
+                    .ret("&'static str")
+                    .line(format!("\"{}\"", &entity.name));
+                opendata_model
+                    .new_fn("entity_set")
+                    .ret("Option<&'static str>")
+                    .line(match set_names.get(qualified_name.as_str()) {
+                        Some(set) => format!("Some(\"{}\")", set),
+                        None => "None".to_string(),
+                    });
+                opendata_model
+                    .new_fn("fields")
+                    .ret(format!(
+                        "&'static [(&'static str, {}::OpenDataType)]",
+                        crate_path
+                    ))
+                    .line(fields);
+
+                if !opts.no_expand {
+                    opendata_model
+                        .new_fn("relations")
+                        .ret("&'static [(&'static str, &'static str, &'static str)]")
+                        .line(format!("&[{}]", expansions));
+                }
+
+                let get_field = opendata_model
+                    .new_fn("get_field")
+                    .arg_ref_self()
+                    .arg(
+                        if entity.properties.is_empty() {
+                            "_name"
+                        } else {
+                            "name"
+                        },
+                        "&str",
+                    )
+                    .ret(format!("Option<{}::OpenDataValue<'_>>", crate_path));
+
+                if entity.properties.is_empty() {
+                    get_field.line("None");
+                } else {
+                    get_field.line("Some(match name {");
+                    // Overridden types can't be turned into reflection values.
+                    for property in entity.properties.iter().filter(|property| {
+                        type_override(&schema.namespace, entity, property, opts).is_none()
+                    }) {
+                        let variant = format!(
+                            "{}::OpenDataValue::{}",
+                            crate_path,
+                            type_mapping(&property.inner).variant
+                        );
+                        let field = property_field_name(entity, property, opts);
+
+                        if !is_nullable(entity, property) {
+                            get_field.line(format!(
+                                "\t{} => {}(&self.{}{}),",
+                                string_literal(&property.name),
+                                variant,
+                                field,
+                                if is_typed_id(entity, property, opts) {
+                                    ".0"
+                                } else {
+                                    ""
+                                }
+                            ));
+                        } else {
+                            let pattern = if opts.triple_state_options {
+                                format!("{}::Patchable::Value(value)", crate_path)
+                            } else {
+                                "Some(value)".to_string()
+                            };
+
+                            get_field
+                                .line(format!(
+                                    "\t{} => match &self.{} {{",
+                                    string_literal(&property.name),
+                                    field
+                                ))
+                                .line(format!("\t\t{} => {}(value),", pattern, variant))
+                                .line(format!("\t\t_ => {}::OpenDataValue::Null,", crate_path))
+                                .line("\t},");
+                        }
+                    }
+                    get_field.line("\t_ => return None,").line("})");
+                }
+            }
+
+            if let Some(set) = set_names.get(qualified_name.as_str()) {
+                head.raw(&format!(
+                    "impl {}::EntitySetName for {} {{\n    const SET_NAME: &'static str = \"{}\";\n}}",
+                    crate_path, entity.name, set
+                ));
+            }
+
+            if opts.decode_collections
+                && derives_deserialize(opts)
+                && set_names.contains_key(qualified_name.as_str())
+            {
+                head.new_impl(&entity.name)
+                    .new_fn("decode_collection")
+                    .attr("cfg(feature = \"serde\")")
+                    .vis(vis)
+                    .arg("bytes", "&[u8]")
+                    .ret(format!(
+                        "Result<{}::ODataResponse<Self>, serde_json::Error>",
+                        crate_path
+                    ))
+                    .line(format!(
+                        "match serde_json::from_slice::<{}::ODataV2Response<Self>>(bytes) {{",
+                        crate_path
+                    ))
+                    .line("\tOk(response) => Ok(response.into()),")
+                    .line("\tErr(_) => serde_json::from_slice(bytes),")
+                    .line("}");
+            }
+
+            if opts.select_macros && !entity.properties.is_empty() {
+                // Exported macros all live at the crate root, so their names include the module
+                // path to keep entities of different schemas apart.
+                let macro_name = schema_path
+                    .split("::")
+                    .skip(1)
+                    .chain(std::iter::once(entity.name.as_str()))
+                    .fold("select".to_string(), |name, segment| {
+                        name + "_" + &segment.to_lowercase()
+                    });
+                let fields: String = entity
+                    .properties
+                    .iter()
+                    .map(|property| {
+                        format!(
+                            "    (@field {}) => {{ \"{}\" }};\n",
+                            property.name, property.name
+                        )
+                    })
+                    .collect();
+
+                if macro_names.insert(macro_name.clone()) {
+                    head.raw(&format!(
+                        indoc! {"
+                            #[macro_export]
+                            macro_rules! {name} {{
+                            {fields}    ($first:ident $(, $rest:ident)* $(,)?) => {{
+                                    concat!($crate::{name}!(@field $first) $(, \",\", $crate::{name}!(@field $rest))*)
+                                }};
+                            }}"},
+                        name = macro_name,
+                        fields = fields
+                    ));
+                } else {
+                    warnings.push(format!(
+                        "not generating {}! for {}, as another entity already uses that name",
+                        macro_name, qualified_name
+                    ));
+                }
+            }
+
+            if opts.eq_ignores_navigations {
+                let comparisons: Vec<_> = entity
+                    .properties
+                    .iter()
+                    .map(|property| property_field_name(entity, property, opts))
+                    .map(|field| format!("self.{} == other.{}", field, field))
+                    .collect();
+
+                head.new_impl(&entity.name)
+                    .impl_trait("PartialEq")
+                    .new_fn("eq")
+                    .arg_ref_self()
+                    .arg("other", "&Self")
+                    .ret("bool")
+                    .line(if comparisons.is_empty() {
+                        "true".to_string()
+                    } else {
+                        comparisons.join(" && ")
+                    });
+            }
+
+            if opts.key_structs || opts.url_builders {
+                if let Some(key) = entity.key_property() {
+                    let key_name = format!("{}Key", entity.name);
+                    let field_name = property_field_name(entity, key, opts);
+                    prelude.push((schema_path.clone(), key_name.clone()));
+
+                    // User-provided types are only required to implement what entities derive.
+                    let overridden = type_override(&schema.namespace, entity, key, opts).is_some();
+                    let copy = is_copy(key) && !overridden;
+
+                    let key_struct = head.new_struct(&key_name);
+                    if original_case {
+                        key_struct.allow("non_snake_case");
+                    }
+
+                    key_struct
+                        .vis(vis)
+                        .r#macro(if is_hashable(key) && !overridden {
+                            "#[derive(Debug, Clone, PartialEq, Eq, Hash)]"
+                        } else {
+                            "#[derive(Debug, Clone, PartialEq)]"
+                        })
+                        .field(
+                            &format!("{} {}", vis, field_name),
+                            edm_type_to_rust_type(&schema.namespace, entity, key, opts),
+                        );
+
+                    head.new_impl(&key_name)
+                        .impl_trait(format!("From<&{}>", entity.name))
+                        .new_fn("from")
+                        .arg("entity", format!("&{}", entity.name))
+                        .ret("Self")
+                        .line(format!(
+                            "{} {{ {}: entity.{}{} }}",
+                            key_name,
+                            field_name,
+                            field_name,
+                            if copy { "" } else { ".clone()" }
+                        ));
+
+                    if copy {
+                        head.new_impl(&entity.name)
+                            .new_fn("id")
+                            .vis(vis)
+                            .arg_ref_self()
+                            .ret(&key_name)
+                            .line(format!("{}::from(self)", key_name));
+                    }
+                }
+            }
+
+            if opts.display {
+                let key = entity.key_property().filter(|key| {
+                    type_override(&schema.namespace, entity, key, opts).is_none()
+                        && !matches!(key.inner, PropertyType::Binary { .. })
+                });
+
+                if let Some(key) = key {
+                    // Strings are quoted like OData key literals, and the durations generated
+                    // for DateTimeOffset keys only implement Debug.
+                    let (format, transform) = match key.inner {
+                        PropertyType::String { .. } => ("'{}'", ".replace('\\'', \"''\")"),
+                        PropertyType::DateTimeOffset { .. } => ("{:?}", ""),
+                        _ => ("{}", ""),
+                    };
+                    head.new_impl(&entity.name)
+                        .impl_trait("std::fmt::Display")
+                        .new_fn("fmt")
+                        .arg_ref_self()
+                        .arg("f", "&mut std::fmt::Formatter<'_>")
+                        .ret("std::fmt::Result")
+                        .line(format!(
+                            "write!(f, \"{}({})\", self.{}{}{})",
+                            entity.name,
+                            format,
+                            property_field_name(entity, key, opts),
+                            if is_typed_id(entity, key, opts) {
+                                ".0"
+                            } else {
+                                ""
+                            },
+                            transform
+                        ));
+                }
+            }
+
+            if opts.url_builders {
+                if let Some(set) = set_names.get(qualified_name.as_str()) {
+                    let urls = head.new_impl(&entity.name);
+                    urls.new_fn("collection_url")
+                        .vis(vis)
+                        .arg("base", "&str")
+                        .ret("String")
+                        .line(format!(
+                            "format!(\"{{}}/{}\", base.trim_end_matches('/'))",
+                            set
+                        ));
+
+                    let key = entity.key_property().filter(|key| {
+                        type_override(&schema.namespace, entity, key, opts).is_none()
+                    });
+                    if let Some((key, (literal, transform))) =
+                        key.and_then(|key| Some((key, key_literal(key, opts)?)))
+                    {
+                        let field_name = property_field_name(entity, key, opts);
+                        urls.new_fn("url")
+                            .vis(vis)
+                            .arg("base", "&str")
+                            .arg("key", format!("&{}Key", entity.name))
+                            .ret("String")
+                            .line(format!(
+                                "format!(\"{{}}/{}({})\", base.trim_end_matches('/'), key.{}{}{})",
+                                set,
+                                literal,
+                                field_name,
+                                if is_typed_id(entity, key, opts) {
+                                    ".0"
+                                } else {
+                                    ""
+                                },
+                                transform
+                            ));
+                    }
+                }
+            }
+
+            if opts.patch_structs {
+                let patch_name = format!("{}Patch", entity.name);
+                let patch = head.new_struct(&patch_name);
+                patch.vis(vis);
+
+                if original_case {
+                    patch.allow("non_snake_case");
+                }
+                patch.doc(&format!(
+                    "Partial {} for PATCH requests, serializing only the properties which are set.",
+                    entity.name
+                ));
+                patch.r#macro("#[derive(Debug, Default)]");
+
+                if opts.non_exhaustive {
+                    patch.r#macro("#[non_exhaustive]");
+                }
+
+                if let Some(derives) = serde_derives(opts) {
+                    patch.r#macro(&derives);
+                }
+
+                if let Some(rule) = rename_rule {
+                    patch.r#macro(&format!(
+                        "#[cfg_attr(feature = \"serde\", serde(rename_all = \"{}\"))]",
+                        rule
+                    ));
+                }
+
+                for property in &entity.properties {
+                    let field_name = property_field_name(entity, property, opts);
+                    let mut field = Field::new(
+                        &format!("{} {}", vis, field_name),
+                        format!(
+                            "Option<{}>",
+                            edm_type_to_rust_type(&schema.namespace, entity, property, opts)
+                        ),
+                    );
+                    let mut annotations = vec![
+                        "#[cfg_attr(feature = \"serde\", serde(default, skip_serializing_if = \"Option::is_none\"))]".to_string(),
+                    ];
+
+                    if property.name != wire_name(field_name.trim_start_matches("r#")) {
+                        annotations.push(format!(
+                            "#[cfg_attr(feature = \"serde\", serde(rename = {}))]",
+                            string_literal(&property.name)
+                        ));
+                    }
+                    field.annotation(annotations.iter().map(String::as_str).collect());
+
+                    patch.push_field(field);
+                }
+
+                let setters = head.new_impl(&patch_name);
+                if original_case {
+                    setters.r#macro("#[allow(non_snake_case)]");
+                }
+                for property in &entity.properties {
+                    let field_name = property_field_name(entity, property, opts);
+
+                    setters
+                        .new_fn(&field_name)
+                        .vis(vis)
+                        .arg_self()
+                        .arg(
+                            "value",
+                            edm_type_to_rust_type(&schema.namespace, entity, property, opts),
+                        )
+                        .ret("Self")
+                        .line(format!("Self {{ {}: Some(value), ..self }}", field_name));
+                }
+            }
+
+            let projection = opts
+                .projections
+                .get(&qualified_name)
+                .or_else(|| opts.projections.get(&entity.name));
+            if let Some(selected) = projection {
+                let properties: Vec<_> = entity
+                    .properties
+                    .iter()
+                    .filter(|property| selected.contains(&property.name))
+                    .collect();
+                let projection_name = format!("{}Projection", entity.name);
+                let projection = head.new_struct(&projection_name);
+                projection.vis(vis);
+
+                if original_case {
+                    projection.allow("non_snake_case");
+                }
+                projection.doc(&format!(
+                    "{} with only the properties requested by `{}::SELECT`.",
+                    entity.name, projection_name
+                ));
+                projection.r#macro("#[derive(Debug)]");
+
+                if let Some(derives) = serde_derives(opts) {
+                    projection.r#macro(&derives);
+                }
+
+                if let Some(rule) = rename_rule {
+                    projection.r#macro(&format!(
+                        "#[cfg_attr(feature = \"serde\", serde(rename_all = \"{}\"))]",
+                        rule
+                    ));
+                }
+
+                for property in &properties {
+                    projection.push_field(property_field(
+                        &schema.namespace,
+                        entity,
+                        property,
+                        &crate_path,
+                        vis,
+                        rename_rule,
+                        opts,
+                    ));
+                }
+
+                let select: Vec<_> = properties
+                    .iter()
+                    .map(|property| property.name.as_str())
+                    .collect();
+                head.raw(&format!(
+                    "impl {} {{\n    /// Value of `$select` requesting the properties of the projection.\n    {} const SELECT: &'static str = {};\n}}",
+                    projection_name,
+                    vis,
+                    string_literal(&select.join(","))
+                ));
+            }
+
+            if opts.validate {
+                let checks: Vec<_> = entity
+                    .properties
+                    .iter()
+                    .filter_map(|property| {
+                        property_validation(&schema.namespace, entity, property, &crate_path, opts)
+                    })
+                    .collect();
+
+                let validate = head
+                    .new_impl(&entity.name)
+                    .new_fn("validate")
+                    .doc("Checks the properties against the MaxLength and Precision facets of the metadata document.")
+                    .vis(vis)
+                    .arg_ref_self()
+                    .ret(format!(
+                        "Result<(), Vec<{}::ValidationError>>",
+                        crate_path
+                    ));
+
+                if checks.is_empty() {
+                    validate.line("Ok(())");
+                } else {
+                    validate.line("let mut errors = Vec::new();");
+                    for line in checks.concat() {
+                        validate.line(line);
+                    }
+                    validate.line("if errors.is_empty() {");
+                    validate.line("\tOk(())");
+                    validate.line("} else {");
+                    validate.line("\tErr(errors)");
+                    validate.line("}");
+                }
+            }
+        }
+
+        if opts.emit_tests {
+            let tests: Vec<String> = ordered(&schema.entities, |entity| &entity.name, opts)
+                .into_iter()
+                .filter(|entity| round_trips(&schema.namespace, entity, opts))
+                .map(|entity| {
+                    format!(
+                        indoc! {"
+                            #[test]
+                            fn {test}_round_trips() {{
+                                let value = super::{name}::default();
+                                let json = serde_json::to_string(&value).unwrap();
+                                let back: super::{name} = serde_json::from_str(&json).unwrap();
+                                assert_eq!(value, back);
+                            }}"},
+                        test = to_snake_case(&entity.name),
+                        name = entity.name
+                    )
+                })
+                .collect();
+
+            if !tests.is_empty() {
+                let tests = tests.join("\n\n").replace('\n', "\n    ");
+                head.raw(&format!(
+                    "#[cfg(all(test, feature = \"serde\"))]\nmod round_trip_tests {{\n    {}\n}}",
+                    tests.replace("\n    \n", "\n\n")
+                ));
+            }
+        }
+
+        if opts.any_entity && !schema.entities.is_empty() {
+            let any_entity = head.new_enum("AnyEntity").vis(vis);
+            any_entity.doc(&format!(
+                "Any entity type of the {} schema. Deserializes into the first entity type, in \
+                 declaration order, which the data matches.",
+                schema.namespace
+            ));
+            any_entity.r#macro("#[derive(Debug)]");
+
+            if let Some(derives) = serde_derives(opts) {
+                any_entity.r#macro(&derives);
+                any_entity.r#macro("#[cfg_attr(feature = \"serde\", serde(untagged))]");
+            }
+
+            for entity in ordered(&schema.entities, |entity| &entity.name, opts) {
+                any_entity.new_variant(&entity.name).tuple(&entity.name);
+            }
+
+            for entity in ordered(&schema.entities, |entity| &entity.name, opts) {
+                head.new_impl("AnyEntity")
+                    .impl_trait(format!("From<{}>", entity.name))
+                    .new_fn("from")
+                    .arg("entity", &entity.name)
+                    .ret("Self")
+                    .line(format!("AnyEntity::{}(entity)", entity.name));
+            }
+        }
+
+        if let Some(sets) = schema.entity_sets() {
+            let sets = ordered(sets, |set| &set.name, opts);
+            if !opts.no_reflection && !sets.is_empty() {
+                let entries: String = sets
+                    .iter()
+                    .map(|set| {
+                        format!(
+                            "    (\"{}\", \"{}\"),\n",
+                            set.name,
+                            set.entity_type.rsplit('.').next().unwrap_or_default()
+                        )
+                    })
+                    .collect();
+
+                head.raw(&format!(
+                    "#[cfg(feature = \"reflection\")]\n{} const ENTITY_SETS: &[(&str, &str)] = &[\n{}];",
+                    vis, entries
+                ));
+            }
+
+            // Sets are re-exported by the name of their entity type. Several sets of the same
+            // type would produce the same import more than once, and types with the same name
+            // from different schemas (or the module itself) can't both be imported.
+            let mut imports: Vec<_> = sets
+                .iter()
+                .map(|set| {
+                    let (namespace, name) = set
+                        .entity_type
+                        .rsplit_once('.')
+                        .unwrap_or(("", &set.entity_type));
+
+                    (
+                        module_path(&crate_path, namespace, strip_prefix, flatten),
+                        name,
+                    )
+                })
+                .collect();
+
+            let mut names: HashMap<&str, String> = schema
+                .entities
+                .iter()
+                .map(|entity| (entity.name.as_str(), schema_path.clone()))
+                .collect();
+            imports.retain(|(path, name)| match names.get(name) {
+                Some(existing) => {
+                    if existing != path {
+                        warnings.push(format!(
+                            "not re-exporting {}::{} from {}, as {} already refers to {}::{}",
+                            path, name, schema_path, name, existing, name
+                        ));
+                    }
+                    false
+                }
+                None => {
+                    names.insert(name, path.clone());
+                    true
+                }
+            });
+
+            if opts.sort_imports {
+                imports.sort();
+            }
+
+            if std::ptr::eq(schema, project.default_schema().unwrap_or(schema)) {
+                default_exports.extend(imports.iter().map(|(_, name)| name.to_string()));
+            }
+
+            for (path, name) in imports {
+                head.import(&path, name).vis(vis);
+            }
+
+            if opts.entity_set_markers {
+                for set in &sets {
+                    let (namespace, name) = set
+                        .entity_type
+                        .rsplit_once('.')
+                        .unwrap_or(("", &set.entity_type));
+                    let key = project
+                        .data_services
+                        .schemas
+                        .iter()
+                        .filter(|target| target.namespace == namespace)
+                        .flat_map(|target| &target.entities)
+                        .find(|entity| entity.name == name)
+                        .and_then(|entity| Some((entity, entity.key_property()?)));
+
+                    let (entity, key) = match key {
+                        Some(key) => key,
+                        None => continue,
+                    };
+
+                    let entity_path = module_path(&crate_path, namespace, strip_prefix, flatten);
+                    let key_type = if opts.key_structs || opts.url_builders {
+                        format!("{}::{}Key", entity_path, name)
+                    } else if is_typed_id(entity, key, opts) {
+                        format!("{}::{}Id", entity_path, name)
+                    } else {
+                        property_rust_type(namespace, entity, key, opts)
+                    };
+
+                    // Sets are commonly named after their entity type, which already takes the name.
+                    let taken = |name: &str| {
+                        names.contains_key(name)
+                            || schema.entities.iter().any(|entity| {
+                                ["Key", "Id", "Patch", "Expanded"]
+                                    .iter()
+                                    .any(|suffix| format!("{}{}", entity.name, suffix) == name)
+                            })
+                    };
+                    let marker = if taken(&set.name) {
+                        format!("{}Set", set.name)
+                    } else {
+                        set.name.clone()
+                    };
+
+                    head.new_struct(&marker)
+                        .vis(vis)
+                        .doc(&format!("Marker for the `{}` entity set.", set.name))
+                        .derive("Debug")
+                        .derive("Clone")
+                        .derive("Copy");
+                    head.raw(&format!(
+                        "impl {}::EntitySet for {} {{\n    type Entity = {}::{};\n    type Key = {};\n    const PATH: &'static str = \"{}\";\n}}",
+                        crate_path, marker, entity_path, name, key_type, set.name
+                    ));
+                }
+            }
+        }
+    }
+
+    let entities: Vec<_> = ordered(
+        &project.data_services.schemas,
+        |schema| &schema.namespace,
+        opts,
+    )
+    .into_iter()
+    .flat_map(|schema| {
+        ordered(&schema.entities, |entity| &entity.name, opts)
+            .into_iter()
+            .map(move |entity| (schema, entity))
+    })
+    .collect();
+
+    if !opts.no_reflection && !entities.is_empty() {
+        let model_info = root.new_struct("ModelInfo").vis(vis);
+        model_info.doc("Reflection of an entity type, as returned by `model_for`.");
+        model_info.r#macro("#[cfg(feature = \"reflection\")]");
+        model_info.derive("Clone").derive("Copy");
+        model_info.field(&format!("{} name", vis), "&'static str");
+        model_info.field(
+            &format!("{} fields", vis),
+            "&'static [(&'static str, OpenDataType)]",
+        );
+        model_info.field(
+            &format!("{} relations", vis),
+            "&'static [(&'static str, &'static str, &'static str)]",
+        );
+        model_info.field(&format!("{} key_fields", vis), "&'static [&'static str]");
+
+        let model_for = root
+            .new_fn("model_for")
+            .doc("Looks up the reflection of an entity type by its namespace-qualified name, or by its\nunqualified name if no other schema has an entity type of the same name.")
+            .attr("cfg(feature = \"reflection\")")
+            .vis(vis)
+            .arg("entity_type", "&str")
+            .ret("Option<ModelInfo>")
+            .line("let (name, fields, relations, key_fields) = match entity_type {");
+
+        for (schema, entity) in &entities {
+            let qualified_name = format!("{}.{}", schema.namespace, entity.name);
+            let unique = entities
+                .iter()
+                .filter(|(_, other)| other.name == entity.name)
+                .count()
+                == 1;
+            let pattern = if unique {
+                format!(
+                    "{} | {}",
+                    string_literal(&qualified_name),
+                    string_literal(&entity.name)
+                )
+            } else {
+                string_literal(&qualified_name)
+            };
+            let path = entity_type_path(
+                &crate_path,
+                &qualified_name,
+                opts.strip_prefix.as_deref(),
+                flatten,
+            );
+
+            model_for
+                .line(format!("\t{} => (", pattern))
+                .line(format!("\t\t<{} as OpenDataModel>::name(),", path))
+                .line(format!("\t\t<{} as OpenDataModel>::fields(),", path))
+                .line(format!("\t\t<{} as OpenDataModel>::relations(),", path))
+                .line(format!(
+                    "\t\t&[{}],",
+                    string_literal(&entity.key.property_ref.name)
+                ))
+                .line("\t),");
+        }
+
+        model_for
+            .line("\t_ => return None,")
+            .line("};")
+            .line("")
+            .line("Some(ModelInfo {")
+            .line("\tname,")
+            .line("\tfields,")
+            .line("\trelations,")
+            .line("\tkey_fields,")
+            .line("})");
+
+        // Targets are looked up by their unqualified name, so navigations to entity types sharing
+        // a name with one in another schema aren't followed.
+        let mut function = Function::new("expand_paths");
+        function.attr("cfg(feature = \"reflection\")");
+        function.arg("relations", "&[(&str, &str, &str)]");
+        function.arg("depth", "usize");
+        function.ret("Vec<String>");
+        function.line("let mut paths = Vec::new();");
+        function.line("if depth == 0 {");
+        function.line("\treturn paths;");
+        function.line("}");
+        function.line("");
+        function.line("for (name, target, _) in relations {");
+        function.line("\tpaths.push(name.to_string());");
+        function.line("\tif let Some(model) = model_for(target) {");
+        function.line("\t\tfor path in expand_paths(model.relations, depth - 1) {");
+        function.line("\t\t\tpaths.push(format!(\"{}/{}\", name, path));");
+        function.line("\t\t}");
+        function.line("\t}");
+        function.line("}");
+        function.line("paths");
+        root.push_fn(function);
+    }
+
+    if opts.fuzzing && derives_deserialize(opts) {
+        let fuzz = root
+            .new_fn("fuzz_deserialize")
+            .attr("cfg(all(feature = \"fuzzing\", feature = \"serde\"))")
+            .vis(vis)
+            .arg("set", "&str")
+            .arg("data", "&[u8]")
+            .ret("Result<(), String>")
+            .line("match set {");
+
+        for schema in ordered(
+            &project.data_services.schemas,
+            |schema| &schema.namespace,
+            opts,
+        ) {
+            for set in ordered(
+                schema.entity_sets().map_or(&[], Vec::as_slice),
+                |set| &set.name,
+                opts,
+            ) {
+                fuzz.line(format!(
+                    "\t\"{}\" => serde_json::from_slice::<{}>(data).map(drop).map_err(|e| e.to_string()),",
+                    set.name,
+                    entity_type_path(
+                        &crate_path,
+                        &set.entity_type,
+                        opts.strip_prefix.as_deref(),
+                        flatten
+                    )
+                ));
+            }
+        }
+
+        fuzz.line("\t_ => Err(format!(\"unknown entity set {}\", set)),")
+            .line("}");
+    }
+
+    if let Some(default_schema) = project
+        .default_schema()
+        .filter(|_| !flatten && !opts.no_glob_reexport)
+    {
+        // Items defined at the root take precedence over the glob import, so any default schema
+        // item sharing a name with one of them is not reachable from the root.
+        let root_names: HashSet<String> = project
+            .data_services
+            .schemas
+            .iter()
+            .filter_map(|schema| {
+                module_segments(&schema.namespace, opts.strip_prefix.as_deref()).pop_front()
+            })
+            .chain(ROOT_ITEMS.iter().map(|name| name.to_string()))
+            .collect();
+
+        for name in default_schema
+            .entities
+            .iter()
+            .map(|entity| &entity.name)
+            .chain(default_exports.iter())
+            .filter(|name| root_names.contains(name.as_str()))
+        {
+            warnings.push(format!(
+                "{} from the default schema is shadowed by a generated item of the same name at the root",
+                name
+            ));
+        }
+
+        let segments = module_segments(&default_schema.namespace, opts.strip_prefix.as_deref());
+        root.import(&Vec::from(segments).join("::"), "*").vis(vis);
+    }
+
+    if opts.prelude {
+        let module = root.new_module("prelude").vis(vis);
+
+        // Types with the same name from different schemas can't both be re-exported.
+        let mut names: HashMap<String, String> = HashMap::new();
+        for (path, name) in prelude {
+            match names.get(&name) {
+                Some(existing) => warnings.push(format!(
+                    "not adding {}::{} to the prelude, as {} already refers to {}::{}",
+                    path, name, name, existing, name
+                )),
+                None => {
+                    module.scope().import(&path, &name).vis(vis);
+                    names.insert(name, path);
+                }
+            }
+        }
+
+        if opts.envelope {
+            module.scope().import(&crate_path, "ODataResponse").vis(vis);
+            module
+                .scope()
+                .import(&crate_path, "ODataV2Response")
+                .vis(vis);
+        }
+
+        if !set_names.is_empty() {
+            module.scope().import(&crate_path, "EntitySetName").vis(vis);
+
+            if opts.entity_set_markers {
+                module.scope().import(&crate_path, "EntitySet").vis(vis);
+            }
+        }
+
+        if !opts.no_reflection {
+            module.scope().raw(&format!(
+                "#[cfg(feature = \"reflection\")]\n{} use {}::{{OpenDataModel, OpenDataType, OpenDataValue}};",
+                vis, crate_path
+            ));
+        }
+    }
+
+    // Prepended rather than pushed onto `outer`, which would put any imports before it.
+    let header = match &opts.header {
+        _ if opts.no_header => String::new(),
+        Some(header) => format!("{}\n\n", header.trim_end()),
+        None => indoc! {"
+            // Code automatically generated using https://github.com/Datavirke/odata-rust-generator
+            // Any changes made to this file may be overwritten by future code generation runs!
+
+        "}
+        .to_string(),
+    };
+
+    // Inner attributes aren't allowed in include!d files, so without a prefix module every item at
+    // the root gets its own.
+    let allows = generated_lints(opts);
+    let (allow, code) = match &opts.module_prefix {
+        _ if !opts.emit_allow || allows.is_empty() => (String::new(), outer.to_string()),
+        Some(_) => (
+            format!("#[allow({})]\n", allows.join(", ")),
+            outer.to_string(),
+        ),
+        None => (
+            String::new(),
+            allow_on_root_items(&outer.to_string(), &allows),
+        ),
+    };
+
+    let output = match &opts.spdx {
+        Some(license) => format!(
+            "// SPDX-License-Identifier: {}\n{}{}{}",
+            license, header, allow, code
+        ),
+        None => format!("{}{}{}", header, allow, code),
+    };
+
+    (output, warnings)
+}
+
+/// Allows `lints` on every item at the root of the rendered `code`. codegen renders root items at
+/// column 0, separated by blank lines, except for consecutive `use` declarations. Clippy rejects
+/// lint attributes on `use` declarations for lints which can't be reported on them, so those only
+/// allow `dead_code` and `unused_imports`.
+fn allow_on_root_items(code: &str, lints: &[&str]) -> String {
+    let use_lints: Vec<_> = lints
+        .iter()
+        .copied()
+        .filter(|lint| ["dead_code", "unused_imports"].contains(lint))
+        .collect();
+
+    let mut output = String::new();
+    let mut item_start = true;
+
+    for line in code.split_inclusive('\n') {
+        let content = line.trim_end_matches('\n');
+        if item_start && !content.is_empty() && !content.starts_with([' ', '\t', '}']) {
+            let is_use = content.starts_with("use ")
+                || (content.starts_with("pub") && content.contains(" use "));
+            let lints = if is_use { &use_lints[..] } else { lints };
+            if !lints.is_empty() {
+                output.push_str(&format!("#[allow({})]\n", lints.join(", ")));
+            }
+        }
+
+        item_start =
+            content.is_empty() || (!content.starts_with([' ', '\t']) && content.ends_with(';'));
+        output.push_str(line);
+    }
+
+    output
+}
+
+/// Rust keywords which can't be used as identifiers without escaping.
+const RUST_KEYWORDS: [&str; 39] = [
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true", "type",
+    "unsafe", "use", "where", "while", "yield",
+];
+
+/// Escapes Rust keywords as raw identifiers. `crate`, `self`, `Self` and `super` can't be raw
+/// identifiers, so they are suffixed with an underscore instead.
+fn escape_keyword(name: &str) -> String {
+    match name {
+        "crate" | "self" | "Self" | "super" => format!("{}_", name),
+        _ if RUST_KEYWORDS.contains(&name) => format!("r#{}", name),
+        _ => name.to_string(),
+    }
+}
+
+/// Rust string literal for `value`, with quotes, backslashes and control characters escaped.
+fn string_literal(value: &str) -> String {
+    format!("{:?}", value)
+}
+
+/// Describes why `name` can't be used as the Rust identifier `identifier`, if it can't.
+fn identifier_problem(kind: &str, name: &str, identifier: &str) -> Option<String> {
+    let mut chars = identifier.trim_start_matches("r#").chars();
+    let valid = matches!(chars.next(), Some(first) if first.is_alphabetic() || first == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_');
+
+    if !valid {
+        Some(format!("{} {} is not a valid Rust identifier", kind, name))
+    } else if RUST_KEYWORDS.contains(&identifier) {
+        Some(format!("{} {} is a reserved Rust keyword", kind, name))
+    } else {
+        None
+    }
+}
+
+/// Lists the problems preventing code from being generated for the metadata document, such as
+/// navigations which can't be resolved and names which aren't valid Rust identifiers.
+pub fn check(project: &Edmx) -> Vec<String> {
+    let mut problems = Vec::new();
+
+    for schema in &project.data_services.schemas {
+        for segment in schema.namespace.split('.') {
+            problems.extend(identifier_problem(
+                "namespace",
+                &schema.namespace,
+                &segment.to_lowercase(),
+            ));
+        }
+
+        for entity in &schema.entities {
+            let qualified_name = format!("{}.{}", schema.namespace, entity.name);
+            problems.extend(identifier_problem(
+                "entity type",
+                &qualified_name,
+                &entity.name,
+            ));
+
+            for property in &entity.properties {
+                problems.extend(identifier_problem(
+                    "property",
+                    &format!("{}.{}", qualified_name, property.name),
+                    &escape_keyword(&property.name.to_lowercase()),
+                ));
+            }
+
+            for navigation in &entity.navigations {
+                let name = format!("{}.{}", qualified_name, navigation.name);
+                problems.extend(identifier_problem(
+                    "navigation",
+                    &name,
+                    &escape_keyword(&navigation.name.to_lowercase()),
+                ));
+
+                if lookup_entity_type(project, schema, navigation).is_none() {
+                    problems.push(format!(
+                        "navigation {} can't be resolved through {} to role {}",
+                        name, navigation.relationship, navigation.to_role
+                    ));
+                }
+            }
+        }
+    }
+
+    problems
+}
+
+/// Lists the options which don't fit the metadata document, such as projections selecting
+/// properties their entity doesn't have.
+pub fn check_options(project: &Edmx, opts: &GeneratorOptions) -> Vec<String> {
+    let mut problems = Vec::new();
+
+    for schema in &project.data_services.schemas {
+        for entity in &schema.entities {
+            let qualified_name = format!("{}.{}", schema.namespace, entity.name);
+            let projection = opts
+                .projections
+                .get(&qualified_name)
+                .or_else(|| opts.projections.get(&entity.name));
+
+            for selected in projection.into_iter().flatten() {
+                if !entity
+                    .properties
+                    .iter()
+                    .any(|property| property.name == *selected)
+                {
+                    problems.push(format!(
+                        "projection of {} selects unknown property {}",
+                        qualified_name, selected
+                    ));
+                }
+            }
+        }
+    }
+
+    problems
+}
+
+/// Merges metadata documents split across several files into one, combining schemas which share
+/// a namespace. Returns `None` if there are no documents.
+pub fn merge_projects(projects: Vec<Edmx>) -> Option<Edmx> {
+    let mut projects = projects.into_iter();
+    let mut merged = projects.next()?;
+
+    for project in projects {
+        for schema in project.data_services.schemas {
+            let existing = merged
+                .data_services
+                .schemas
+                .iter_mut()
+                .find(|existing| existing.namespace == schema.namespace);
+
+            match existing {
+                Some(existing) => {
+                    existing.entities.extend(schema.entities);
+                    existing.associations.extend(schema.associations);
+
+                    match (&mut existing.entity_container, schema.entity_container) {
+                        (Some(container), Some(other)) => {
+                            container.entity_sets.extend(other.entity_sets);
+                            container.association_sets.extend(other.association_sets);
+                        }
+                        (container, other) => {
+                            if container.is_none() {
+                                *container = other;
+                            }
+                        }
+                    }
+                }
+                None => merged.data_services.schemas.push(schema),
+            }
+        }
+    }
+
+    Some(merged)
+}
+
+/// Cargo.toml for a standalone crate named `name` holding the code generated for the metadata
+/// document in its `src/lib.rs`, with the features and dependencies from `cargo_features`.
+pub fn cargo_manifest(name: &str, project: &Edmx, opts: &GeneratorOptions) -> String {
+    format!(
+        "[package]\nname = \"{}\"\nversion = \"0.1.0\"\nedition = \"2018\"\n\n{}",
+        name,
+        cargo_features(project, opts)
+    )
+}
+
+/// Recommends the Cargo `[features]` and `[dependencies]` needed to build the code generated for
+/// the metadata document with the given options.
+pub fn cargo_features(project: &Edmx, opts: &GeneratorOptions) -> String {
+    let entities = || {
+        project
+            .data_services
+            .schemas
+            .iter()
+            .flat_map(|schema| schema.entities.iter().map(move |entity| (schema, entity)))
+    };
+    let uses_datetime = entities().any(|(_, entity)| {
+        entity
+            .properties
+            .iter()
+            .any(|property| matches!(property.inner, PropertyType::DateTime { .. }))
+    });
+    let uses_rust_decimal = opts.decimal_type.as_deref() == Some("rust-decimal")
+        && entities().any(|(_, entity)| {
+            entity
+                .properties
+                .iter()
+                .any(|property| matches!(property.inner, PropertyType::Decimal { .. }))
+        });
+    // Unresolved navigations are generated as serde_json::Value whether or not serde is enabled.
+    let uses_json_values = !opts.no_expand
+        && entities().any(|(schema, entity)| {
+            entity
+                .navigations
+                .iter()
+                .any(|navigation| lookup_entity_type(project, schema, navigation).is_none())
+        });
+    // serde only implements its traits for Arc and Rc with the rc feature.
+    let uses_shared_navigations = shared_nav_ptr(opts).is_some()
+        && !opts.no_expand
+        && entities().any(|(schema, entity)| {
+            entity
+                .navigations
+                .iter()
+                .any(|navigation| lookup_entity_type(project, schema, navigation).is_some())
+        });
+    let serde = !opts.no_serde;
+    let serde_json = serde && (opts.fuzzing || opts.decode_collections);
+    // OpenDataModel::to_json is only available with both serde and reflection enabled.
+    let to_json = opts.to_json && serde && !opts.no_reflection && derives_serialize(opts);
+
+    let mut default = Vec::new();
+    let mut features = Vec::new();
+    let mut dependencies = Vec::new();
+
+    if serde {
+        let mut enables = vec!["\"dep:serde\""];
+        if serde_json && !uses_json_values {
+            enables.push("\"dep:serde_json\"");
+        }
+        if uses_datetime {
+            enables.push(match opts.datetime_type.as_deref() {
+                Some("time") => "\"time/serde-well-known\"",
+                _ => "\"chrono/serde\"",
+            });
+        }
+        if uses_rust_decimal {
+            enables.push("\"rust_decimal/serde\"");
+        }
+
+        default.push("\"serde\"");
+        features.push(format!("serde = [{}]", enables.join(", ")));
+        dependencies.push(format!(
+            "serde = {{ version = \"1\", features = [{}], optional = true }}",
+            if uses_shared_navigations {
+                "\"derive\", \"rc\""
+            } else {
+                "\"derive\""
+            }
+        ));
+    }
+
+    if !opts.no_reflection {
+        default.push("\"reflection\"");
+        features.push(if to_json && !uses_json_values {
+            "reflection = [\"dep:serde_json\"]".to_string()
+        } else {
+            "reflection = []".to_string()
+        });
+    }
+
+    if opts.fuzzing && serde {
+        features.push("fuzzing = []".to_string());
+    }
+
+    if uses_json_values {
+        dependencies.push("serde_json = \"1\"".to_string());
+    } else if serde_json || to_json {
+        dependencies.push("serde_json = { version = \"1\", optional = true }".to_string());
+    }
+
+    if uses_datetime {
+        dependencies.push(match opts.datetime_type.as_deref() {
+            Some("time") => "time = \"0.3\"".to_string(),
+            _ => "chrono = \"0.4\"".to_string(),
+        });
+    }
+
+    if uses_rust_decimal {
+        dependencies.push("rust_decimal = \"1\"".to_string());
+    }
+
+    features.insert(0, format!("default = [{}]", default.join(", ")));
+
+    let mut manifest = format!(
+        "[features]\n{}\n\n[dependencies]\n{}\n",
+        features.join("\n"),
+        dependencies.join("\n")
+    );
+
+    // The generated round trip tests go through serde_json.
+    if opts.emit_tests && serde && !uses_json_values {
+        manifest.push_str("\n[dev-dependencies]\nserde_json = \"1\"\n");
+    }
+
+    manifest
+}
+
+/// Generates code for the metadata document at `metadata_path` into `file_name` within
+/// `$OUT_DIR`, for use from a build script. Cargo is told to re-run the build script whenever
+/// the metadata document changes, and shows the warnings of `generate_with_warnings`.
+///
+/// ```no_run
+/// // build.rs
+/// use odata_rust_generator::{generate_to_out_dir, GeneratorOptions};
+///
+/// fn main() {
+///     generate_to_out_dir("metadata.xml", "odata.rs", &GeneratorOptions::default());
+/// }
+/// ```
+///
+/// The generated code can then be included from the crate itself:
+///
+/// ```ignore
+/// include!(concat!(env!("OUT_DIR"), "/odata.rs"));
+/// ```
+pub fn generate_to_out_dir<P: AsRef<Path>>(
+    metadata_path: P,
+    file_name: &str,
+    opts: &GeneratorOptions,
+) -> PathBuf {
+    let metadata_path = metadata_path.as_ref();
+    println!("cargo:rerun-if-changed={}", metadata_path.display());
+
+    let source = std::fs::read_to_string(metadata_path).expect("failed to read metadata document");
+    let project = Edmx::from_str(&source).expect("failed to parse metadata document");
+
+    let problems = check_options(&project, opts);
+    if !problems.is_empty() {
+        panic!("invalid generator options: {}", problems.join(", "));
+    }
+
+    let (output, warnings) = generate_with_warnings(&project, opts);
+    for warning in &warnings {
+        println!("cargo:warning={}", warning);
+    }
+
+    let output_file =
+        Path::new(&std::env::var_os("OUT_DIR").expect("OUT_DIR is not set")).join(file_name);
+    write_if_changed(&output_file, &output).expect("failed to write output to file");
+
+    output_file
+}
+
+/// Writes `contents` to `path` unless the file already holds exactly that, leaving its
+/// modification time untouched so the generated code isn't needlessly recompiled. Returns whether
+/// the file was written.
+pub fn write_if_changed<P: AsRef<Path>>(path: P, contents: &str) -> std::io::Result<bool> {
+    let path = path.as_ref();
+    if std::fs::read(path).is_ok_and(|existing| existing == contents.as_bytes()) {
+        return Ok(false);
+    }
+
+    std::fs::write(path, contents).map(|_| true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use odata_parser_rs::{Key, PropertyRef};
+
+    fn generate_northwind(opts: GeneratorOptions) -> String {
+        let source = std::fs::read_to_string("tests/northwind.xml").unwrap();
+        let project = Edmx::from_str(&source).unwrap();
+
+        generate(&project, &opts)
+    }
+
+    #[test]
+    fn test_generate_from_parsed_edmx() {
+        let output = generate_northwind(GeneratorOptions::default());
+
+        assert!(output.contains("pub struct Customer {"));
+    }
+
+    #[test]
+    fn test_rename_all_replaces_per_field_renames() {
+        let output = generate_northwind(GeneratorOptions {
+            rename_all: true,
+            ..Default::default()
+        });
+
+        assert!(output.contains("serde(rename_all = \"PascalCase\")"));
+        assert!(output.contains("serde(rename = \"CompanyName\")"));
+        assert!(!output.contains("serde(rename = \"Phone\")"));
+        assert!(!output.contains("serde(rename = \"Orders\", default)"));
+
+        let output = generate_northwind(GeneratorOptions::default());
+        assert!(!output.contains("rename_all"));
+        assert!(output.contains("serde(rename = \"Phone\")"));
+    }
+
+    #[test]
+    fn test_lenient_bools() {
+        let output = generate_northwind(GeneratorOptions {
+            lenient_bools: true,
+            ..Default::default()
+        });
+
+        assert!(output.contains("fn lenient_bool<'de, D>(de: D) -> Result<bool, D::Error>"));
+        assert!(output.contains("LenientBool::Int(1) => Ok(true),"));
+        assert!(output.contains("serde(deserialize_with = \"crate::lenient_bool\")"));
+
+        let output = generate_northwind(GeneratorOptions::default());
+        assert!(!output.contains("lenient_bool"));
+    }
+
+    #[test]
+    fn test_unused_root_helpers_are_skipped() {
+        // Discontinued is Northwind's only Boolean, and isn't nullable.
+        let output = generate_northwind(GeneratorOptions {
+            lenient_bools: true,
+            ..Default::default()
+        });
+        assert!(output.contains("fn lenient_bool<'de, D>"));
+        assert!(!output.contains("fn lenient_optional_bool<'de, D>"));
+
+        // Nullable properties are all Patchable, which doesn't go through the helpers.
+        let output = generate_northwind(GeneratorOptions {
+            lenient_numbers: true,
+            triple_state_options: true,
+            ..Default::default()
+        });
+        assert!(output.contains("fn lenient_number<'de, D, T>"));
+        assert!(!output.contains("fn lenient_optional_number<'de, D, T>"));
+        assert!(!output.contains("fn empty_string_as_none<'de, D, T>"));
+
+        // Every nullable property is handled by a helper of its own.
+        let output = generate_northwind(GeneratorOptions {
+            empty_values_are_null: true,
+            lenient_numbers: true,
+            datetime_type: Some("time".to_string()),
+            ..Default::default()
+        });
+        assert!(!output.contains("EmptyValue"));
+        assert!(!output.contains("empty_value_as_none"));
+    }
+
+    #[test]
+    fn test_key_structs() {
+        let output = generate_northwind(GeneratorOptions {
+            key_structs: true,
+            ..Default::default()
+        });
+
+        assert!(output.contains(
+            "#[derive(Debug, Clone, PartialEq, Eq, Hash)]\n    \
+             pub struct OrderKey {\n        pub orderid: i32,"
+        ));
+        assert!(output.contains("impl From<&Order> for OrderKey {"));
+        assert!(output.contains("OrderKey { orderid: entity.orderid }"));
+        assert!(output.contains("pub fn id(&self) -> OrderKey {"));
+
+        assert!(output.contains(
+            "#[derive(Debug, Clone, PartialEq, Eq, Hash)]\n    \
+             pub struct CustomerKey {"
+        ));
+
+        // String keys aren't Copy, so no id() shortcut is generated for them
+        assert!(output.contains("CustomerKey { customerid: entity.customerid.clone() }"));
+        assert!(!output.contains("pub fn id(&self) -> CustomerKey {"));
+    }
+
+    #[test]
+    fn test_empty_values_are_null() {
+        let output = generate_northwind(GeneratorOptions {
+            empty_values_are_null: true,
+            ..Default::default()
+        });
+
+        assert!(output.contains("fn empty_value_as_none<'de, D, T>(de: D)"));
+        assert!(output.contains(
+            "#[cfg_attr(feature = \"serde\", serde(default))]\n        \
+             #[cfg_attr(feature = \"serde\", serde(deserialize_with = \"crate::empty_value_as_none\"))]\n        \
+             #[cfg_attr(feature = \"serde\", serde(rename = \"UnitsInStock\"))]\n        \
+             pub unitsinstock: Option<i16>,"
+        ));
+        assert!(output.contains(
+            "serde(deserialize_with = \"crate::empty_value_as_none\"))]\n        \
+             #[cfg_attr(feature = \"serde\", serde(rename = \"OrderDate\"))]\n        \
+             pub orderdate: Option<chrono::NaiveDateTime>,"
+        ));
+
+        // Strings keep using empty_string_as_none, and non-nullable properties are left alone
+        assert!(output.contains(
+            "serde(deserialize_with = \"crate::empty_string_as_none\"))]\n        \
+             #[cfg_attr(feature = \"serde\", serde(rename = \"ContactName\"))]\n        \
+             pub contactname: Option<String>,"
+        ));
+        assert!(output.contains(
+            "serde(rename = \"ProductName\"))]\n        \
+             pub productname: String,"
+        ));
+        assert!(output.contains(
+            "serde(rename = \"UnitsInStock\"))]\n        \
+             pub unitsinstock: Option<i16>,\n        \
+             #[cfg_attr(feature = \"serde\", serde(rename = \"Discontinued\"))]\n        \
+             pub discontinued: bool,"
+        ));
+    }
+
+    #[test]
+    fn test_cargo_features() {
+        let source = std::fs::read_to_string("tests/northwind.xml").unwrap();
+        let project = Edmx::from_str(&source).unwrap();
+
+        assert_eq!(
+            cargo_features(&project, &GeneratorOptions::default()),
+            indoc! {r#"
+                [features]
+                default = ["serde", "reflection"]
+                serde = ["dep:serde", "chrono/serde"]
+                reflection = []
+
+                [dependencies]
+                serde = { version = "1", features = ["derive"], optional = true }
+                chrono = "0.4"
+            "#}
+        );
+
+        assert_eq!(
+            cargo_features(
+                &project,
+                &GeneratorOptions {
+                    to_json: true,
+                    ..Default::default()
+                }
+            ),
+            indoc! {r#"
+                [features]
+                default = ["serde", "reflection"]
+                serde = ["dep:serde", "chrono/serde"]
+                reflection = ["dep:serde_json"]
+
+                [dependencies]
+                serde = { version = "1", features = ["derive"], optional = true }
+                serde_json = { version = "1", optional = true }
+                chrono = "0.4"
+            "#}
+        );
+
+        assert_eq!(
+            cargo_features(
+                &project,
+                &GeneratorOptions {
+                    no_reflection: true,
+                    fuzzing: true,
+                    datetime_type: Some("time".to_string()),
+                    ..Default::default()
+                }
+            ),
+            indoc! {r#"
+                [features]
+                default = ["serde"]
+                serde = ["dep:serde", "dep:serde_json", "time/serde-well-known"]
+                fuzzing = []
+
+                [dependencies]
+                serde = { version = "1", features = ["derive"], optional = true }
+                serde_json = { version = "1", optional = true }
+                time = "0.3"
+            "#}
+        );
+
+        assert_eq!(
+            cargo_features(
+                &project,
+                &GeneratorOptions {
+                    no_serde: true,
+                    no_reflection: true,
+                    ..Default::default()
+                }
+            ),
+            "[features]\ndefault = []\n\n[dependencies]\nchrono = \"0.4\"\n"
+        );
+
+        assert!(cargo_features(
+            &project,
+            &GeneratorOptions {
+                emit_tests: true,
+                ..Default::default()
+            }
+        )
+        .ends_with("\n[dev-dependencies]\nserde_json = \"1\"\n"));
+
+        assert_eq!(
+            cargo_features(
+                &project,
+                &GeneratorOptions {
+                    no_reflection: true,
+                    decimal_type: Some("rust-decimal".to_string()),
+                    ..Default::default()
+                }
+            ),
+            indoc! {r#"
+                [features]
+                default = ["serde"]
+                serde = ["dep:serde", "chrono/serde", "rust_decimal/serde"]
+
+                [dependencies]
+                serde = { version = "1", features = ["derive"], optional = true }
+                chrono = "0.4"
+                rust_decimal = "1"
+            "#}
+        );
+    }
+
+    #[test]
+    fn test_cargo_manifest() {
+        let source = std::fs::read_to_string("tests/northwind.xml").unwrap();
+        let project = Edmx::from_str(&source).unwrap();
+
+        assert_eq!(
+            cargo_manifest("northwind", &project, &GeneratorOptions::default()),
+            format!(
+                "[package]\nname = \"northwind\"\nversion = \"0.1.0\"\nedition = \"2018\"\n\n{}",
+                cargo_features(&project, &GeneratorOptions::default())
+            )
+        );
+    }
+
+    #[test]
+    fn test_sorted_output_is_deterministic() {
+        let source = std::fs::read_to_string("tests/northwind.xml").unwrap();
+        let reordered = source.replacen(
+            r#"<EntitySet Name="Categories" EntityType="NorthwindModel.Category"/>"#,
+            "",
+            1,
+        );
+        let reordered = reordered.replacen(
+            r#"<EntitySet Name="Products" EntityType="NorthwindModel.Product"/>"#,
+            r#"<EntitySet Name="Products" EntityType="NorthwindModel.Product"/>
+        <EntitySet Name="Categories" EntityType="NorthwindModel.Category"/>"#,
+            1,
+        );
+        assert_ne!(source, reordered);
+
+        let generate_from = |source: &str, unsorted| {
+            generate(
+                &Edmx::from_str(source).unwrap(),
+                &GeneratorOptions {
+                    unsorted,
+                    ..Default::default()
+                },
+            )
+        };
+
+        assert_eq!(generate_from(&source, false), generate_from(&source, false));
+        assert_eq!(
+            generate_from(&source, false),
+            generate_from(&reordered, false)
+        );
+        assert_ne!(
+            generate_from(&source, true),
+            generate_from(&reordered, true)
+        );
+
+        let output = generate_from(&source, false);
+        assert!(
+            output.find("pub mod default").unwrap()
+                < output.find("pub mod northwindmodel").unwrap()
+        );
+        assert!(output
+            .contains("(\"Categories\", \"Category\"),\n        (\"Customers\", \"Customer\"),"));
+    }
+
+    #[test]
+    fn test_type_overrides() {
+        let output = generate_northwind(GeneratorOptions {
+            type_overrides: vec![
+                (
+                    "NorthwindModel.Category.Description".to_string(),
+                    TypeOverride {
+                        rust_type: "crate::Markdown".to_string(),
+                        serde_with: None,
+                    },
+                ),
+                (
+                    "NorthwindModel.Customer.Phone".to_string(),
+                    TypeOverride {
+                        rust_type: "crate::PhoneNumber".to_string(),
+                        serde_with: Some("crate::phone_number".to_string()),
+                    },
+                ),
+            ]
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        });
+
+        assert!(output.contains(
+            "#[cfg_attr(feature = \"serde\", serde(default))]\n        \
+             #[cfg_attr(feature = \"serde\", serde(rename = \"Description\"))]\n        \
+             pub description: Option<crate::Markdown>,"
+        ));
+        assert!(output.contains(
+            "#[cfg_attr(feature = \"serde\", serde(default))]\n        \
+             #[cfg_attr(feature = \"serde\", serde(with = \"crate::phone_number\"))]\n        \
+             #[cfg_attr(feature = \"serde\", serde(rename = \"Phone\"))]\n        \
+             pub phone: Option<crate::PhoneNumber>,"
+        ));
+
+        // Overridden properties aren't reflected as values
+        assert!(!output.contains("\"Description\" => match &self.description {"));
+        assert!(output.contains("\"ContactName\" => match &self.contactname {"));
+    }
+
+    #[test]
+    fn test_type_overrides_of_keys() {
+        let output = generate_northwind(GeneratorOptions {
+            key_structs: true,
+            type_overrides: vec![(
+                "NorthwindModel.Order.OrderID".to_string(),
+                TypeOverride {
+                    rust_type: "crate::OrderNumber".to_string(),
+                    serde_with: None,
+                },
+            )]
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        });
+
+        // Int32 keys are Copy and hashable, but the overriding type may be neither.
+        assert!(output.contains(
+            "#[derive(Debug, Clone, PartialEq)]\n    \
+             pub struct OrderKey {\n        \
+             pub orderid: crate::OrderNumber,"
+        ));
+        assert!(output.contains("OrderKey { orderid: entity.orderid.clone() }"));
+        assert!(!output.contains("pub fn id(&self) -> OrderKey {"));
+
+        assert!(output.contains(
+            "#[derive(Debug, Clone, PartialEq, Eq, Hash)]\n    pub struct CustomerKey {"
+        ));
+    }
+
+    #[test]
+    fn test_url_builders() {
+        let output = generate_northwind(GeneratorOptions {
+            url_builders: true,
+            ..Default::default()
+        });
+
+        assert!(output.contains("pub struct CustomerKey {"));
+        assert!(output.contains(
+            "pub fn collection_url(base: &str) -> String {\n            \
+             format!(\"{}/Customers\", base.trim_end_matches('/'))"
+        ));
+        assert!(output.contains(
+            "pub fn url(base: &str, key: &CustomerKey) -> String {\n            \
+             format!(\"{}/Customers('{}')\", base.trim_end_matches('/'), key.customerid.replace('\\'', \"''\"))"
+        ));
+        assert!(output.contains(
+            "pub fn url(base: &str, key: &OrderKey) -> String {\n            \
+             format!(\"{}/Orders({})\", base.trim_end_matches('/'), key.orderid)"
+        ));
+
+        let output = generate_northwind(GeneratorOptions {
+            url_builders: true,
+            typed_ids: true,
+            ..Default::default()
+        });
+        assert!(output.contains("base.trim_end_matches('/'), key.orderid.0)"));
+    }
+
+    #[test]
+    fn test_lowercase_navigation_collections_default() {
+        let project = Edmx::from_str(indoc! {r#"
+            <edmx:Edmx Version="1.0" xmlns:edmx="http://schemas.microsoft.com/ado/2007/06/edmx">
+              <edmx:DataServices>
+                <Schema Namespace="Shop">
+                  <EntityType Name="basket">
+                    <Key><PropertyRef Name="id"/></Key>
+                    <Property Name="id" Type="Edm.Int32" Nullable="false"/>
+                    <NavigationProperty Name="items" Relationship="Shop.basket_items" ToRole="items" FromRole="basket"/>
+                  </EntityType>
+                  <EntityType Name="item">
+                    <Key><PropertyRef Name="id"/></Key>
+                    <Property Name="id" Type="Edm.Int32" Nullable="false"/>
+                  </EntityType>
+                  <Association Name="basket_items">
+                    <End Type="Shop.basket" Role="basket" Multiplicity="1"/>
+                    <End Type="Shop.item" Role="items" Multiplicity="*"/>
+                  </Association>
+                </Schema>
+              </edmx:DataServices>
+            </edmx:Edmx>
+        "#})
+        .unwrap();
+
+        let output = generate(&project, &GeneratorOptions::default());
+
+        assert!(output.contains(
+            "#[cfg_attr(feature = \"serde\", serde(default))]\n        \
+             pub items: Vec<item>,"
+        ));
+    }
+
+    #[test]
+    fn test_required_navigations() {
+        let project = Edmx::from_str(indoc! {r#"
+            <edmx:Edmx Version="1.0" xmlns:edmx="http://schemas.microsoft.com/ado/2007/06/edmx">
+              <edmx:DataServices>
+                <Schema Namespace="Shop">
+                  <EntityType Name="Basket">
+                    <Key><PropertyRef Name="Id"/></Key>
+                    <Property Name="Id" Type="Edm.Int32" Nullable="false"/>
+                    <NavigationProperty Name="Items" Relationship="Shop.Basket_Items" ToRole="Items" FromRole="Basket"/>
+                  </EntityType>
+                  <EntityType Name="Item">
+                    <Key><PropertyRef Name="Id"/></Key>
+                    <Property Name="Id" Type="Edm.Int32" Nullable="false"/>
+                    <NavigationProperty Name="Basket" Relationship="Shop.Basket_Items" ToRole="Basket" FromRole="Items"/>
+                  </EntityType>
+                  <Association Name="Basket_Items">
+                    <End Type="Shop.Basket" Role="Basket" Multiplicity="1"/>
+                    <End Type="Shop.Item" Role="Items" Multiplicity="*"/>
+                  </Association>
+                </Schema>
+              </edmx:DataServices>
+            </edmx:Edmx>
+        "#})
+        .unwrap();
+
+        let output = generate(&project, &GeneratorOptions::default());
+        assert!(output.contains(
+            "#[cfg_attr(feature = \"serde\", serde(rename = \"Basket\", default))]\n        \
+             pub basket: Option<Box<Basket>>,"
+        ));
+
+        let output = generate(
+            &project,
+            &GeneratorOptions {
+                required_navigations: true,
+                nav_accessors: true,
+                ..Default::default()
+            },
+        );
+        assert!(output.contains(
+            "#[cfg_attr(feature = \"serde\", serde(rename = \"Basket\"))]\n        \
+             pub basket: Box<Basket>,"
+        ));
+        assert!(output.contains("pub fn basket(&self) -> &Basket {\n            &self.basket"));
+        assert!(output.contains("pub items: Vec<Item>,"));
+    }
+
+    #[test]
+    fn test_nav_ptr() {
+        for (nav_ptr, single, many) in [
+            (None, "Option<Box<Customer>>", "Vec<Order>"),
+            (Some("box"), "Option<Box<Customer>>", "Vec<Order>"),
+            (
+                Some("arc"),
+                "Option<std::sync::Arc<Customer>>",
+                "Vec<std::sync::Arc<Order>>",
+            ),
+            (
+                Some("rc"),
+                "Option<std::rc::Rc<Customer>>",
+                "Vec<std::rc::Rc<Order>>",
+            ),
+        ] {
+            let output = generate_northwind(GeneratorOptions {
+                nav_ptr: nav_ptr.map(str::to_string),
+                nav_accessors: true,
+                ..Default::default()
+            });
+
+            assert!(output.contains(&format!("pub customer: {},", single)));
+            assert!(output.contains(&format!("pub orders: {},", many)));
+            assert!(output.contains("self.customer.as_deref()"));
+        }
+
+        let output = generate_northwind(GeneratorOptions {
+            nav_ptr: Some("arc".to_string()),
+            nav_accessors: true,
+            ..Default::default()
+        });
+        assert!(output.contains("self.orders.iter().map(std::ops::Deref::deref)"));
+
+        let source = std::fs::read_to_string("tests/northwind.xml").unwrap();
+        let project = Edmx::from_str(&source).unwrap();
+        assert!(cargo_features(
+            &project,
+            &GeneratorOptions {
+                nav_ptr: Some("rc".to_string()),
+                ..Default::default()
+            }
+        )
+        .contains("serde = { version = \"1\", features = [\"derive\", \"rc\"], optional = true }"));
+    }
+
+    #[test]
+    fn test_merge_projects() {
+        let sales = Edmx::from_str(indoc! {r#"
+            <edmx:Edmx Version="1.0" xmlns:edmx="http://schemas.microsoft.com/ado/2007/06/edmx">
+              <edmx:DataServices>
+                <Schema Namespace="Sales">
+                  <EntityType Name="Order">
+                    <Key><PropertyRef Name="Id"/></Key>
+                    <Property Name="Id" Type="Edm.Int32" Nullable="false"/>
+                    <NavigationProperty Name="Customer" Relationship="Crm.Order_Customer" ToRole="Customer" FromRole="Order"/>
+                  </EntityType>
+                </Schema>
+                <Schema Namespace="Default">
+                  <EntityContainer Name="Container">
+                    <EntitySet Name="Orders" EntityType="Sales.Order"/>
+                  </EntityContainer>
+                </Schema>
+              </edmx:DataServices>
+            </edmx:Edmx>
+        "#})
+        .unwrap();
+        let crm = Edmx::from_str(indoc! {r#"
+            <edmx:Edmx Version="1.0" xmlns:edmx="http://schemas.microsoft.com/ado/2007/06/edmx">
+              <edmx:DataServices>
+                <Schema Namespace="Crm">
+                  <EntityType Name="Customer">
+                    <Key><PropertyRef Name="Id"/></Key>
+                    <Property Name="Id" Type="Edm.Int32" Nullable="false"/>
+                  </EntityType>
+                  <Association Name="Order_Customer">
+                    <End Type="Sales.Order" Role="Order" Multiplicity="*"/>
+                    <End Type="Crm.Customer" Role="Customer" Multiplicity="0..1"/>
+                  </Association>
+                </Schema>
+                <Schema Namespace="Default">
+                  <EntityContainer Name="Container">
+                    <EntitySet Name="Customers" EntityType="Crm.Customer"/>
+                  </EntityContainer>
+                </Schema>
+              </edmx:DataServices>
+            </edmx:Edmx>
+        "#})
+        .unwrap();
+
+        let project = merge_projects(vec![sales, crm]).unwrap();
+        assert_eq!(project.data_services.schemas.len(), 3);
+        assert!(check(&project).is_empty());
+
+        let output = generate(&project, &GeneratorOptions::default());
+        assert!(output.contains("pub customer: Option<Box<crate::crm::Customer>>,"));
+        assert!(output.contains("pub use crate::sales::Order;"));
+        assert!(output.contains("pub use crate::crm::Customer;"));
+        assert_eq!(output.matches("pub enum OpenDataType {").count(), 1);
+
+        assert!(merge_projects(Vec::new()).is_none());
+    }
+
+    #[test]
+    fn test_display() {
+        let output = generate_northwind(GeneratorOptions {
+            display: true,
+            ..Default::default()
+        });
+
+        assert!(output.contains(
+            "impl std::fmt::Display for Order {\n        \
+             fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {\n            \
+             write!(f, \"Order({})\", self.orderid)"
+        ));
+        assert!(output
+            .contains("write!(f, \"Customer('{}')\", self.customerid.replace('\\'', \"''\"))"));
+
+        let project = Edmx::from_str(indoc! {r#"
+            <edmx:Edmx Version="1.0" xmlns:edmx="http://schemas.microsoft.com/ado/2007/06/edmx">
+              <edmx:DataServices>
+                <Schema Namespace="Calendar">
+                  <EntityType Name="Event">
+                    <Key><PropertyRef Name="Start"/></Key>
+                    <Property Name="Start" Type="Edm.DateTimeOffset" Nullable="false"/>
+                  </EntityType>
+                  <EntityType Name="Attachment">
+                    <Key><PropertyRef Name="Hash"/></Key>
+                    <Property Name="Hash" Type="Edm.Binary" Nullable="false"/>
+                  </EntityType>
+                </Schema>
+              </edmx:DataServices>
+            </edmx:Edmx>
+        "#})
+        .unwrap();
+        let output = generate(
+            &project,
+            &GeneratorOptions {
+                display: true,
+                ..Default::default()
+            },
+        );
+
+        assert!(output.contains("write!(f, \"Event({:?})\", self.start)"));
+        assert!(!output.contains("std::fmt::Display for Attachment"));
+    }
+
+    #[test]
+    fn test_flatten_single_schema() {
+        let project = Edmx::from_str(indoc! {r#"
+            <edmx:Edmx Version="1.0" xmlns:edmx="http://schemas.microsoft.com/ado/2007/06/edmx">
+              <edmx:DataServices>
+                <Schema Namespace="Com.Example.Shop">
+                  <EntityType Name="Basket">
+                    <Key><PropertyRef Name="Id"/></Key>
+                    <Property Name="Id" Type="Edm.Int32" Nullable="false"/>
+                    <NavigationProperty Name="Items" Relationship="Com.Example.Shop.Basket_Items" ToRole="Items" FromRole="Basket"/>
+                  </EntityType>
+                  <EntityType Name="Item">
+                    <Key><PropertyRef Name="Id"/></Key>
+                    <Property Name="Id" Type="Edm.Int32" Nullable="false"/>
+                  </EntityType>
+                  <Association Name="Basket_Items">
+                    <End Type="Com.Example.Shop.Basket" Role="Basket" Multiplicity="1"/>
+                    <End Type="Com.Example.Shop.Item" Role="Items" Multiplicity="*"/>
+                  </Association>
+                  <EntityContainer Name="Container">
+                    <EntitySet Name="Baskets" EntityType="Com.Example.Shop.Basket"/>
+                  </EntityContainer>
+                </Schema>
+              </edmx:DataServices>
+            </edmx:Edmx>
+        "#})
+        .unwrap();
+
+        let output = generate(
+            &project,
+            &GeneratorOptions {
+                flatten_single_schema: true,
+                ..Default::default()
+            },
+        );
+
+        assert!(!output.contains("pub mod "));
+        assert!(output.contains("\npub struct Basket {"));
+        assert!(output.contains(
+            "    /// See [`crate::Item`]\n    \
+             #[cfg_attr(feature = \"serde\", serde(rename = \"Items\", default))]\n    \
+             pub items: Vec<Item>,"
+        ));
+        assert!(output.contains("\npub fn entity_types()"));
+        assert!(!output.contains("pub use "));
+
+        // Several schemas are still generated as modules
+        let output = generate_northwind(GeneratorOptions {
+            flatten_single_schema: true,
+            ..Default::default()
+        });
+        assert!(output.contains("pub mod northwindmodel {"));
+    }
+
+    #[test]
+    fn test_keyword_fields_are_escaped() {
+        let project = Edmx::from_str(indoc! {r#"
+            <edmx:Edmx Version="1.0" xmlns:edmx="http://schemas.microsoft.com/ado/2007/06/edmx">
+              <edmx:DataServices>
+                <Schema Namespace="Catalog">
+                  <EntityType Name="Product">
+                    <Key><PropertyRef Name="Id"/></Key>
+                    <Property Name="Id" Type="Edm.Int32" Nullable="false"/>
+                    <Property Name="Match" Type="Edm.String"/>
+                    <Property Name="Self" Type="Edm.String"/>
+                    <NavigationProperty Name="Type" Relationship="Catalog.Product_Type" ToRole="Type" FromRole="Product"/>
+                  </EntityType>
+                  <EntityType Name="ProductType">
+                    <Key><PropertyRef Name="Id"/></Key>
+                    <Property Name="Id" Type="Edm.Int32" Nullable="false"/>
+                  </EntityType>
+                  <Association Name="Product_Type">
+                    <End Type="Catalog.Product" Role="Product" Multiplicity="*"/>
+                    <End Type="Catalog.ProductType" Role="Type" Multiplicity="0..1"/>
+                  </Association>
+                </Schema>
+              </edmx:DataServices>
+            </edmx:Edmx>
+        "#})
+        .unwrap();
+
+        let output = generate(&project, &GeneratorOptions::default());
+
+        assert!(output.contains(
+            "#[cfg_attr(feature = \"serde\", serde(rename = \"Match\"))]\n        \
+             pub r#match: Option<String>,"
+        ));
+        assert!(output.contains(
+            "#[cfg_attr(feature = \"serde\", serde(rename = \"Self\"))]\n        \
+             pub self_: Option<String>,"
+        ));
+        assert!(output.contains(
+            "#[cfg_attr(feature = \"serde\", serde(rename = \"Type\", default))]\n        \
+             pub r#type: Option<Box<ProductType>>,"
+        ));
+        assert!(check(&project).is_empty());
+
+        let output = generate(
+            &project,
+            &GeneratorOptions {
+                field_case: Some("original".to_string()),
+                ..Default::default()
+            },
+        );
+        assert!(output.contains("pub Self_: Option<String>,"));
+        assert!(output.contains("pub Type: Option<Box<ProductType>>,"));
+    }
+
+    #[test]
+    fn test_emit_tests() {
+        let output = generate_northwind(GeneratorOptions {
+            emit_tests: true,
+            typed_ids: true,
+            ..Default::default()
+        });
+
+        assert!(output.contains(
+            "#[derive(Debug)]\n    \
+             #[cfg_attr(test, derive(Default, PartialEq))]\n    \
+             #[cfg_attr(feature = \"serde\", derive(serde::Serialize, serde::Deserialize))]\n    \
+             pub struct Order {"
+        ));
+        assert!(output.contains("#[cfg_attr(test, derive(Default))]\n    #[cfg_attr(feature = \"serde\", derive(serde::Serialize, serde::Deserialize))]\n    #[cfg_attr(feature = \"serde\", serde(transparent))]\n    pub struct OrderId(pub i32);"));
+        assert!(output.contains(indoc! {r#"
+            #[cfg(all(test, feature = "serde"))]
+                mod round_trip_tests {
+                    #[test]
+                    fn category_round_trips() {
+                        let value = super::Category::default();
+                        let json = serde_json::to_string(&value).unwrap();
+                        let back: super::Category = serde_json::from_str(&json).unwrap();
+                        assert_eq!(value, back);
+                    }
+
+                    #[test]
+                    fn customer_round_trips() {"#}));
+
+        // Entities whose keys aren't serialized can't be read back
+        let output = generate_northwind(GeneratorOptions {
+            emit_tests: true,
+            skip_serializing_keys: true,
+            ..Default::default()
+        });
+        assert!(!output.contains("round_trip_tests"));
+    }
+
+    #[test]
+    fn test_no_glob_reexport() {
+        let output = generate_northwind(GeneratorOptions::default());
+        assert!(output.contains("pub use default::*;"));
+
+        let output = generate_northwind(GeneratorOptions {
+            no_glob_reexport: true,
+            ..Default::default()
+        });
+        assert!(!output.contains("pub use default::*;"));
+        assert!(
+            output.contains("pub use crate::northwindmodel::{Category, Customer, Order, Product};")
+        );
+    }
+
+    #[test]
+    fn test_entity_set_markers() {
+        let output = generate_northwind(GeneratorOptions {
+            entity_set_markers: true,
+            ..Default::default()
+        });
+
+        assert!(output.contains(
+            "pub trait EntitySet {\n    type Entity;\n    type Key;\n    const PATH: &'static str;\n}"
+        ));
+        assert!(output.contains(
+            "/// Marker for the `Customers` entity set.\n    \
+             #[derive(Debug, Clone, Copy)]\n    \
+             pub struct Customers;"
+        ));
+        assert!(output.contains(
+            "impl crate::EntitySet for Customers {\n        \
+             type Entity = crate::northwindmodel::Customer;\n        \
+             type Key = String;\n        \
+             const PATH: &'static str = \"Customers\";\n    \
+             }"
+        ));
+
+        let output = generate_northwind(GeneratorOptions {
+            entity_set_markers: true,
+            key_structs: true,
+            ..Default::default()
+        });
+        assert!(output.contains("    type Key = crate::northwindmodel::OrderKey;\n"));
+        assert!(!generate_northwind(GeneratorOptions::default()).contains("EntitySet "));
+    }
+
+    #[test]
+    fn test_emit_allow() {
+        let output = generate_northwind(GeneratorOptions {
+            emit_allow: true,
+            any_entity: true,
+            field_case: Some("original".to_string()),
+            ..Default::default()
+        });
+        assert!(!output.contains("#![allow"));
+        assert!(output.contains(
+            "overwritten by future code generation runs!\n\n\
+             pub use default::*;\n\n\
+             #[allow(non_snake_case, clippy::large_enum_variant)]\n\
+             #[cfg(feature = \"serde\")]\n\
+             fn empty_string_as_none<'de, D, T>(de: D)"
+        ));
+        assert!(output.contains(
+            "#[allow(non_snake_case, clippy::large_enum_variant)]\n\
+             pub mod northwindmodel {"
+        ));
+        assert!(output.contains(
+            "#[allow(non_snake_case, clippy::large_enum_variant)]\n\
+             #[cfg(feature = \"reflection\")]\n\
+             pub trait OpenDataModel {"
+        ));
+        assert!(!output.contains("#[allow(non_snake_case, clippy::large_enum_variant)]\n}"));
+        assert!(!output.contains("#[allow(non_snake_case, clippy::large_enum_variant)]\nwhere"));
+
+        let output = generate_northwind(GeneratorOptions {
+            emit_allow: true,
+            module_prefix: Some("northwind".to_string()),
+            visibility: Some("pub(crate)".to_string()),
+            triple_state_options: true,
+            ..Default::default()
+        });
+        assert!(output.contains(
+            "#[allow(dead_code, unused_imports, clippy::derivable_impls)]\n\
+             pub(crate) mod northwind {"
+        ));
+
+        let output = generate_northwind(GeneratorOptions {
+            emit_allow: true,
+            visibility: Some("pub(crate)".to_string()),
+            triple_state_options: true,
+            ..Default::default()
+        });
+        assert!(output.contains(
+            "#[allow(dead_code, unused_imports)]\n\
+             pub(crate) use default::*;"
+        ));
+        assert!(output.contains(
+            "#[allow(dead_code, unused_imports, clippy::derivable_impls)]\n\
+             impl<T> Default for Patchable<T> {"
+        ));
+
+        let output = generate_northwind(GeneratorOptions {
+            emit_allow: true,
+            ..Default::default()
+        });
+        assert!(!output.contains("allow(dead_code"));
+        assert!(!output.contains("#![allow"));
+    }
+
+    #[test]
+    fn test_prelude() {
+        let output = generate_northwind(GeneratorOptions {
+            prelude: true,
+            key_structs: true,
+            envelope: true,
+            ..Default::default()
+        });
+
+        assert!(output.contains(
+            "pub mod prelude {\n    \
+             pub use crate::northwindmodel::{Category, CategoryKey, Customer, CustomerKey, Order, OrderKey, Product, ProductKey};\n    \
+             pub use crate::{ODataResponse, ODataV2Response, EntitySetName};\n\n    \
+             #[cfg(feature = \"reflection\")]\n    \
+             pub use crate::{OpenDataModel, OpenDataType, OpenDataValue};\n\
+             }"
+        ));
+        assert!(!generate_northwind(GeneratorOptions::default()).contains("mod prelude"));
+    }
+
+    #[test]
+    fn test_root_name_conflicts() {
+        let project = Edmx::from_str(indoc! {r#"
+            <edmx:Edmx Version="1.0" xmlns:edmx="http://schemas.microsoft.com/ado/2007/06/edmx">
+              <edmx:DataServices>
+                <Schema Namespace="Default">
+                  <EntityType Name="ModelInfo">
+                    <Key><PropertyRef Name="Id"/></Key>
+                    <Property Name="Id" Type="Edm.Int32" Nullable="false"/>
+                  </EntityType>
+                </Schema>
+                <Schema Namespace="Warehouse">
+                  <EntityType Name="ModelInfo">
+                    <Key><PropertyRef Name="Id"/></Key>
+                    <Property Name="Id" Type="Edm.Int32" Nullable="false"/>
+                  </EntityType>
+                </Schema>
+              </edmx:DataServices>
+            </edmx:Edmx>
+        "#})
+        .unwrap();
+
+        let (_, warnings) = generate_with_warnings(
+            &project,
+            &GeneratorOptions {
+                prelude: true,
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(
+            warnings,
+            vec![
+                "ModelInfo from the default schema is shadowed by a generated item of the same name at the root",
+                "not adding crate::warehouse::ModelInfo to the prelude, as ModelInfo already refers to crate::default::ModelInfo",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_nav_accessors() {
+        let output = generate_northwind(GeneratorOptions {
+            nav_accessors: true,
+            ..Default::default()
+        });
+
+        assert!(output.contains(
+            "pub fn orders(&self) -> impl Iterator<Item = &Order> + '_ {\n            \
+             self.orders.iter()"
+        ));
+        assert!(output.contains(
+            "pub fn customer(&self) -> Option<&Customer> {\n            \
+             self.customer.as_deref()"
+        ));
+
+        let output = generate_northwind(GeneratorOptions {
+            nav_accessors: true,
+            group_navigations: true,
+            ..Default::default()
+        });
+        assert!(output.contains("impl OrderExpanded {\n        pub fn customer(&self)"));
+    }
+
+    #[test]
+    fn test_typed_ids() {
+        let output = generate_northwind(GeneratorOptions {
+            typed_ids: true,
+            key_structs: true,
+            ..Default::default()
+        });
+
+        assert!(output.contains(
+            "#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]\n    \
+             #[cfg_attr(feature = \"serde\", derive(serde::Serialize, serde::Deserialize))]\n    \
+             #[cfg_attr(feature = \"serde\", serde(transparent))]\n    \
+             pub struct OrderId(pub i32);"
+        ));
+        assert!(output.contains("impl std::ops::Deref for OrderId {"));
+        assert!(output.contains("impl From<i32> for OrderId {"));
+        assert!(output.contains("pub orderid: OrderId,"));
+        assert!(output.contains("pub struct OrderKey {\n        pub orderid: OrderId,"));
+        assert!(output.contains("\"OrderID\" => crate::OpenDataValue::Int32(&self.orderid.0),"));
+
+        // Only the key is wrapped, so foreign keys keep their plain types
+        assert!(output.contains("pub struct CustomerId(pub String);"));
+        assert!(output.contains("impl AsRef<str> for CustomerId {"));
+        assert!(output.contains("impl std::borrow::Borrow<str> for CustomerId {"));
+        assert!(!output.contains("impl AsRef<str> for OrderId {"));
+        assert!(output.contains("pub customerid: Option<String>,"));
+    }
+
+    #[test]
+    fn test_spdx_header_is_first_line() {
+        let output = generate_northwind(GeneratorOptions {
+            spdx: Some("Apache-2.0".to_string()),
+            ..Default::default()
+        });
+
+        assert_eq!(
+            output.lines().next(),
+            Some("// SPDX-License-Identifier: Apache-2.0")
+        );
+        assert!(output.contains("// Code automatically generated using"));
+    }
+
+    #[test]
+    fn test_eq_ignores_navigations() {
+        let output = generate_northwind(GeneratorOptions {
+            eq_ignores_navigations: true,
+            ..Default::default()
+        });
+
+        assert!(output.contains("impl PartialEq for Order {"));
+        assert!(output.contains(
+            "self.orderid == other.orderid && self.customerid == other.customerid && \
+             self.orderdate == other.orderdate && self.freight == other.freight && \
+             self.shipname == other.shipname\n"
+        ));
+        assert!(!output.contains("self.customer == other.customer"));
+    }
+
+    #[test]
+    fn test_binary_properties_use_base64() {
+        let output = generate_northwind(GeneratorOptions::default());
+
+        assert!(output.contains(
+            "#[cfg_attr(feature = \"serde\", serde(serialize_with = \"crate::serialize_optional_base64\", deserialize_with = \"crate::deserialize_optional_base64\"))]\n        \
+             #[cfg_attr(feature = \"serde\", serde(rename = \"Picture\"))]\n        \
+             pub picture: Option<Vec<u8>>,"
+        ));
+        assert!(output.contains("fn base64_decode(text: &str) -> Option<Vec<u8>> {"));
+        // Only the helpers used by the document's binary properties are generated.
+        assert!(!output.contains("fn deserialize_base64"));
+
+        let output = generate_northwind(GeneratorOptions {
+            serde_mode: Some("de".to_string()),
+            ..Default::default()
+        });
+        assert!(
+            output.contains("serde(deserialize_with = \"crate::deserialize_optional_base64\"))]")
+        );
+        assert!(!output.contains("base64_encode"));
+        assert!(!generate_northwind(GeneratorOptions {
+            no_serde: true,
+            ..Default::default()
+        })
+        .contains("base64"));
+    }
+
+    #[test]
+    fn test_model_for() {
+        let output = generate_northwind(GeneratorOptions::default());
+
+        assert!(output.contains(
+            "#[derive(Clone, Copy)]\n\
+             #[cfg(feature = \"reflection\")]\n\
+             pub struct ModelInfo {\n    \
+             pub name: &'static str,\n    \
+             pub fields: &'static [(&'static str, OpenDataType)],\n    \
+             pub relations: &'static [(&'static str, &'static str, &'static str)],\n    \
+             pub key_fields: &'static [&'static str],\n\
+             }"
+        ));
+        assert!(output.contains(
+            "#[cfg(feature = \"reflection\")]\n\
+             pub fn model_for(entity_type: &str) -> Option<ModelInfo> {\n    \
+             let (name, fields, relations, key_fields) = match entity_type {\n    \
+             \t\"NorthwindModel.Category\" | \"Category\" => (\n    \
+             \t\t<crate::northwindmodel::Category as OpenDataModel>::name(),\n    \
+             \t\t<crate::northwindmodel::Category as OpenDataModel>::fields(),\n    \
+             \t\t<crate::northwindmodel::Category as OpenDataModel>::relations(),\n    \
+             \t\t&[\"CategoryID\"],\n    \
+             \t),"
+        ));
+        assert!(!generate_northwind(GeneratorOptions {
+            no_reflection: true,
+            ..Default::default()
+        })
+        .contains("model_for"));
+    }
+
+    #[test]
+    fn test_expandable_paths() {
+        let output = generate_northwind(GeneratorOptions::default());
+
+        assert!(output.contains(
+            "    /// Paths which can be $expand-ed from the entity, following navigations up to `depth` levels deep.\n    \
+             fn expandable_paths(depth: usize) -> Vec<String> {\n        \
+             expand_paths(Self::relations(), depth)\n    \
+             }"
+        ));
+        assert!(output.contains(
+            "#[cfg(feature = \"reflection\")]\n\
+             fn expand_paths(relations: &[(&str, &str, &str)], depth: usize) -> Vec<String> {"
+        ));
+        assert!(output.contains("\t\t\tpaths.push(format!(\"{}/{}\", name, path));"));
+    }
+
+    #[test]
+    fn test_reflection_to_json() {
+        let output = generate_northwind(GeneratorOptions {
+            to_json: true,
+            ..Default::default()
+        });
+
+        assert!(output.contains(
+            "    /// Serializes the entity into a JSON value keyed by the OData property names.\n    \
+             #[cfg(feature = \"serde\")]\n    \
+             fn to_json(&self) -> Result<serde_json::Value, serde_json::Error>\n    \
+             where Self: serde::Serialize,\n    \
+             {\n        \
+             serde_json::to_value(self)\n    \
+             }"
+        ));
+
+        for opts in [
+            GeneratorOptions::default(),
+            GeneratorOptions {
+                to_json: true,
+                serde_mode: Some("de".to_string()),
+                ..Default::default()
+            },
+            GeneratorOptions {
+                to_json: true,
+                no_reflection: true,
+                ..Default::default()
+            },
+        ] {
+            assert!(!generate_northwind(opts).contains("fn to_json"));
+        }
+    }
+
+    #[test]
+    fn test_reflection_carries_facets() {
+        let entity = EntityType {
+            name: "Customer".to_string(),
+            key: Key {
+                property_ref: PropertyRef {
+                    name: "CustomerID".to_string(),
+                },
+            },
+            properties: vec![
+                Property {
+                    name: "CustomerID".to_string(),
+                    inner: PropertyType::String {
+                        precision: None,
+                        max_length: Some(5),
+                        fixed_length: Some(5),
+                    },
+                    nullable: false,
+                },
+                Property {
+                    name: "Balance".to_string(),
+                    inner: PropertyType::Decimal {
+                        precision: Some(19),
+                        default: None,
+                    },
+                    nullable: true,
+                },
+            ],
+            navigations: Vec::new(),
+        };
+
+        assert_eq!(
+            entity_type_reflection(
+                &entity,
+                "crate",
+                &GeneratorOptions {
+                    unsorted: true,
+                    ..Default::default()
+                }
+            ),
+            "&[(\"CustomerID\", crate::OpenDataType::String { nullable: false, key: true, max_length: Some(5), fixed_length: Some(5) }), \
+             (\"Balance\", crate::OpenDataType::Decimal { nullable: true, key: false, precision: Some(19) })]"
+        );
+
+        let output = generate_northwind(GeneratorOptions::default());
+        assert!(output.contains("max_length: Option<u32>,"));
+        assert!(output.contains("fixed_length: Option<u32>,"));
+        assert!(output.contains("precision: Option<u8>,"));
+    }
+
+    #[test]
+    fn test_reflection_facets_of_parsed_metadata() {
+        // Northwind declares MaxLength="5" and FixedLength="5" on Customer.CustomerID and Precision="19"
+        // on Order.Freight, but the parser drops all three facets.
+        let output = generate_northwind(GeneratorOptions::default());
+
+        assert!(
+            output.contains("odata-parser-rs doesn't read MaxLength, FixedLength or Precision yet")
+        );
+        assert!(output.contains(
+            "(\"CustomerID\", crate::OpenDataType::String { nullable: false, key: true, max_length: None, fixed_length: None })"
+        ));
+        assert!(output.contains(
+            "(\"Freight\", crate::OpenDataType::Decimal { nullable: true, key: false, precision: None })"
+        ));
+    }
+
+    #[test]
+    fn test_edm_names() {
+        let output = generate_northwind(GeneratorOptions::default());
+
+        assert!(output.contains("pub fn edm_name(&self) -> &'static str {"));
+        for edm_type in [
+            "Binary",
+            "Boolean",
+            "Byte",
+            "DateTime",
+            "DateTimeOffset",
+            "Decimal",
+            "Double",
+            "Int16",
+            "Int32",
+            "String",
+        ] {
+            assert!(output.contains(&format!(
+                "OpenDataType::{} {{ .. }} => \"Edm.{}\",",
+                edm_type, edm_type
+            )));
+        }
+
+        assert!(!generate_northwind(GeneratorOptions {
+            no_reflection: true,
+            ..Default::default()
+        })
+        .contains("fn edm_name"));
+    }
+
+    #[test]
+    fn test_module_prefix_rewrites_crate_paths() {
+        let output = generate_northwind(GeneratorOptions {
+            module_prefix: Some("northwind".to_string()),
+            lenient_bools: true,
+            ..Default::default()
+        });
+
+        assert!(output.contains("pub mod northwind {"));
+        assert!(output.contains("crate::northwind::empty_string_as_none"));
+        assert!(output.contains("crate::northwind::lenient_bool"));
+        assert!(output.contains("impl crate::northwind::OpenDataModel for Order"));
+        assert!(output.contains("crate::northwind::OpenDataType::Int32"));
+        assert!(output.contains("pub use crate::northwind::northwindmodel::"));
+        assert_eq!(
+            output.matches("crate::").count(),
+            output.matches("crate::northwind::").count()
+        );
+    }
+
+    #[test]
+    fn test_navigation_doc_links() {
+        let output = generate_northwind(GeneratorOptions::default());
+
+        assert!(output.contains(
+            "/// See [`crate::northwindmodel::Order`]\n        \
+             #[cfg_attr(feature = \"serde\", serde(rename = \"Orders\", default))]\n        \
+             pub orders: Vec<Order>,"
+        ));
+        assert!(output.contains("/// See [`crate::northwindmodel::Customer`]"));
+    }
+
+    #[test]
+    fn test_type_mapping_covers_every_property_type() {
+        let mappings: Vec<_> = property_type_prototypes()
+            .iter()
+            .map(|prototype| {
+                let mapping = type_mapping(prototype);
+                (mapping.rust_type, mapping.variant)
+            })
+            .collect();
+
+        assert_eq!(
+            mappings,
+            vec![
+                ("Vec<u8>", "Binary"),
+                ("bool", "Boolean"),
+                ("u8", "Byte"),
+                ("chrono::NaiveDateTime", "DateTime"),
+                ("std::time::Duration", "DateTimeOffset"),
+                ("f64", "Decimal"),
+                ("f64", "Double"),
+                ("i16", "Int16"),
+                ("i32", "Int32"),
+                ("String", "String"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fuzz_deserialize_dispatches_on_entity_sets() {
+        let output = generate_northwind(GeneratorOptions {
+            fuzzing: true,
+            ..Default::default()
+        });
+
+        assert!(output.contains("#[cfg(all(feature = \"fuzzing\", feature = \"serde\"))]"));
+        assert!(output
+            .contains("pub fn fuzz_deserialize(set: &str, data: &[u8]) -> Result<(), String> {"));
+        assert!(output.contains(
+            "\"Customers\" => serde_json::from_slice::<crate::northwindmodel::Customer>(data)"
+        ));
+        assert!(output.contains("_ => Err(format!(\"unknown entity set {}\", set)),"));
+
+        assert!(!generate_northwind(GeneratorOptions::default()).contains("fuzz_deserialize"));
+    }
+
+    #[test]
+    fn test_group_navigations_into_expanded_structs() {
+        let output = generate_northwind(GeneratorOptions {
+            group_navigations: true,
+            ..Default::default()
+        });
+
+        assert!(output.contains("pub struct Customer {"));
+        assert!(output.contains(
+            "pub struct CustomerExpanded {\n        \
+             #[cfg_attr(feature = \"serde\", serde(flatten))]\n        \
+             pub base: Customer,"
+        ));
+        assert!(output.contains("impl std::ops::Deref for CustomerExpanded {"));
+        assert!(output.contains("impl From<CustomerExpanded> for Customer {"));
+
+        let customer = output
+            .split("pub struct Customer {")
+            .nth(1)
+            .and_then(|rest| rest.split('}').next())
+            .unwrap();
+        assert!(!customer.contains("pub orders"));
+        assert!(output.contains("pub orders: Vec<Order>,"));
+    }
+
+    #[test]
+    fn test_entity_sets_constant() {
+        let output = generate_northwind(GeneratorOptions::default());
+
+        assert!(output.contains(
+            "    #[cfg(feature = \"reflection\")]\n    \
+             pub const ENTITY_SETS: &[(&str, &str)] = &[\n        \
+             (\"Categories\", \"Category\"),\n        \
+             (\"Customers\", \"Customer\"),\n        \
+             (\"Orders\", \"Order\"),\n        \
+             (\"Products\", \"Product\"),\n    \
+             ];"
+        ));
+        assert!(!generate_northwind(GeneratorOptions {
+            no_reflection: true,
+            ..Default::default()
+        })
+        .contains("ENTITY_SETS"));
+    }
+
+    #[test]
+    fn test_relations_carry_cardinality() {
+        let output = generate_northwind(GeneratorOptions::default());
+
+        assert!(output
+            .contains("fn relations() -> &'static [(&'static str, &'static str, &'static str)];"));
+        assert!(output.contains("&[(\"Orders\", \"Order\", \"many\")]"));
+        assert!(output.contains("&[(\"Customer\", \"Customer\", \"optional\")]"));
+        assert_eq!(cardinality("1"), "one");
+    }
+
+    #[test]
+    fn test_namespace_docs() {
+        let output = generate_northwind(GeneratorOptions {
+            namespace_docs: true,
+            ..Default::default()
+        });
+
+        assert!(output.contains(
+            "    /// OData entity NorthwindModel.Order (set: Orders)\n    \
+             #[derive(Debug)]"
+        ));
+        assert!(!generate_northwind(GeneratorOptions::default()).contains("/// OData entity"));
+    }
+
+    #[test]
+    fn test_strip_prefix_flattens_module_paths() {
+        let source = std::fs::read_to_string("tests/folketinget.xml").unwrap();
+        let project = Edmx::from_str(&source).unwrap();
+        let output = generate(
+            &project,
+            &GeneratorOptions {
+                strip_prefix: Some("FT.Domain".to_string()),
+                fuzzing: true,
+                ..Default::default()
+            },
+        );
+
+        assert!(output.contains("pub mod models {"));
+        assert!(!output.contains("pub mod ft {"));
+        assert!(output.contains("pub use crate::models::{"));
+        assert!(output.contains("serde_json::from_slice::<crate::models::Afstemning>"));
+
+        assert_eq!(
+            module_segments("FT.Domain.Models", Some("FT")),
+            vec!["domain", "models"]
+        );
+        assert_eq!(
+            module_segments("FT.Domain", Some("FT.Domain")),
+            vec!["ft", "domain"]
+        );
+        assert_eq!(
+            module_segments("FTX.Domain", Some("FT")),
+            vec!["ftx", "domain"]
+        );
+    }
+
+    #[test]
+    fn test_triple_state_options() {
+        let output = generate_northwind(GeneratorOptions {
+            triple_state_options: true,
+            ..Default::default()
+        });
+
+        assert!(output.contains("pub enum Patchable<T> {"));
+        assert!(output.contains("impl<'de, T> serde::Deserialize<'de> for Patchable<T>"));
+        assert!(output.contains(
+            "#[cfg_attr(feature = \"serde\", serde(default, skip_serializing_if = \"crate::Patchable::is_missing\"))]\n        \
+             #[cfg_attr(feature = \"serde\", serde(rename = \"ShipName\"))]\n        \
+             pub shipname: crate::Patchable<String>,"
+        ));
+        assert!(output.contains("pub orderid: i32,"));
+        assert!(!output.contains("empty_string_as_none\"))]"));
+    }
+
+    #[test]
+    fn test_response_envelopes() {
+        let output = generate_northwind(GeneratorOptions {
+            envelope: true,
+            ..Default::default()
+        });
+
+        assert!(output.contains(
+            "pub struct ODataResponse<T> {\n    \
+             pub value: Vec<T>,\n    \
+             #[cfg_attr(feature = \"serde\", serde(rename = \"@odata.nextLink\", default))]\n    \
+             pub next_link: Option<String>,"
+        ));
+        assert!(output.contains("pub struct ODataV2Response<T> {\n    pub d: ODataV2Results<T>,"));
+        assert!(output.contains("serde(rename = \"__next\", default)"));
+        assert!(output.contains("impl<T> From<ODataV2Response<T>> for ODataResponse<T> {"));
+        assert!(!generate_northwind(GeneratorOptions::default()).contains("ODataResponse"));
+    }
+
+    #[test]
+    fn test_decode_collections() {
+        let output = generate_northwind(GeneratorOptions {
+            decode_collections: true,
+            ..Default::default()
+        });
+
+        assert!(output.contains("pub struct ODataResponse<T> {"));
+        assert!(output.contains(
+            "pub fn decode_collection(bytes: &[u8]) -> Result<crate::ODataResponse<Self>, serde_json::Error> {\n            \
+             match serde_json::from_slice::<crate::ODataV2Response<Self>>(bytes) {"
+        ));
+        assert_eq!(output.matches("pub fn decode_collection(").count(), 4);
+    }
+
+    #[test]
+    fn test_crate_visibility() {
+        let output = generate_northwind(GeneratorOptions {
+            visibility: Some("pub(crate)".to_string()),
+            key_structs: true,
+            ..Default::default()
+        });
+
+        assert!(output.contains("pub(crate) mod northwindmodel {"));
+        assert!(output.contains("pub(crate) struct Customer {"));
+        assert!(output.contains("pub(crate) customerid: String,"));
+        assert!(output.contains("pub(crate) fn id(&self) -> OrderKey {"));
+        assert!(output.contains("pub(crate) use crate::northwindmodel::{"));
+        assert!(output.contains("pub(crate) use default::*;"));
+        assert!(!output.contains("pub struct"));
+        assert!(!output.contains("pub mod"));
+        assert!(!output
+            .lines()
+            .any(|line| line.trim_start().starts_with("pub ")));
+    }
+
+    #[test]
+    fn test_entity_set_imports_are_sorted_and_deduplicated() {
+        let project = Edmx::from_str(indoc! {r#"
+            <edmx:Edmx Version="1.0" xmlns:edmx="http://schemas.microsoft.com/ado/2007/06/edmx">
+              <edmx:DataServices>
+                <Schema Namespace="Sales">
+                  <EntityType Name="Order">
+                    <Key><PropertyRef Name="Id"/></Key>
+                    <Property Name="Id" Type="Edm.Int32" Nullable="false"/>
+                  </EntityType>
+                  <EntityType Name="Customer">
+                    <Key><PropertyRef Name="Id"/></Key>
+                    <Property Name="Id" Type="Edm.Int32" Nullable="false"/>
+                  </EntityType>
+                </Schema>
+                <Schema Namespace="Default">
+                  <EntityContainer Name="Container">
+                    <EntitySet Name="Orders" EntityType="Sales.Order"/>
+                    <EntitySet Name="Customers" EntityType="Sales.Customer"/>
+                    <EntitySet Name="ArchivedOrders" EntityType="Sales.Order"/>
+                  </EntityContainer>
+                </Schema>
+              </edmx:DataServices>
+            </edmx:Edmx>
+        "#})
+        .unwrap();
+
+        let output = generate(
+            &project,
+            &GeneratorOptions {
+                sort_imports: true,
+                ..Default::default()
+            },
+        );
+        assert!(output.contains("pub use crate::sales::{Customer, Order};"));
+
+        let output = generate(&project, &GeneratorOptions::default());
+        assert!(output.contains("pub use crate::sales::{Order, Customer};"));
+    }
+
+    #[test]
+    fn test_select_macros() {
+        let output = generate_northwind(GeneratorOptions {
+            select_macros: true,
+            ..Default::default()
+        });
+
+        assert!(output.contains(indoc! {r#"
+                #[macro_export]
+                macro_rules! select_northwindmodel_order {
+                    (@field OrderID) => { "OrderID" };
+                    (@field CustomerID) => { "CustomerID" };
+                    (@field OrderDate) => { "OrderDate" };
+                    (@field Freight) => { "Freight" };
+                    (@field ShipName) => { "ShipName" };
+                    ($first:ident $(, $rest:ident)* $(,)?) => {
+                        concat!($crate::select_northwindmodel_order!(@field $first) $(, ",", $crate::select_northwindmodel_order!(@field $rest))*)
+                    };
+                }"#}
+            .lines()
+            .map(|line| format!("    {}", line))
+            .collect::<Vec<_>>()
+            .join("\n")
+            .as_str()));
+    }
+
+    #[test]
+    fn test_select_macro_names_are_unique() {
+        let project = Edmx::from_str(indoc! {r#"
+            <edmx:Edmx Version="1.0" xmlns:edmx="http://schemas.microsoft.com/ado/2007/06/edmx">
+              <edmx:DataServices>
+                <Schema Namespace="Shop">
+                  <EntityType Name="Item">
+                    <Key><PropertyRef Name="Id"/></Key>
+                    <Property Name="Id" Type="Edm.Int32" Nullable="false"/>
+                  </EntityType>
+                  <EntityType Name="item">
+                    <Key><PropertyRef Name="Id"/></Key>
+                    <Property Name="Id" Type="Edm.Int32" Nullable="false"/>
+                  </EntityType>
+                </Schema>
+                <Schema Namespace="Warehouse">
+                  <EntityType Name="Item">
+                    <Key><PropertyRef Name="Id"/></Key>
+                    <Property Name="Id" Type="Edm.Int32" Nullable="false"/>
+                  </EntityType>
+                </Schema>
+              </edmx:DataServices>
+            </edmx:Edmx>
+        "#})
+        .unwrap();
+
+        let (output, warnings) = generate_with_warnings(
+            &project,
+            &GeneratorOptions {
+                select_macros: true,
+                ..Default::default()
+            },
+        );
+
+        // Names differing only by case can't be told apart, so only the first one is generated.
+        assert_eq!(output.matches("macro_rules! select_shop_item {").count(), 1);
+        assert_eq!(
+            warnings,
+            vec!["not generating select_shop_item! for Shop.item, as another entity already uses that name"]
+        );
+        assert_eq!(
+            output
+                .matches("macro_rules! select_warehouse_item {")
+                .count(),
+            1
+        );
+
+        let output = generate(
+            &project,
+            &GeneratorOptions {
+                select_macros: true,
+                module_prefix: Some("odata".to_string()),
+                ..Default::default()
+            },
+        );
+
+        assert!(output.contains("macro_rules! select_odata_shop_item {"));
+    }
+
+    #[test]
+    fn test_navigations_are_heap_allocated() {
+        let source = std::fs::read_to_string("tests/folketinget.xml").unwrap();
+        let project = Edmx::from_str(&source).unwrap();
+        let output = generate(&project, &GeneratorOptions::default());
+
+        // Afstemning navigates to both single and collection-valued relations, neither of
+        // which may be stored inline.
+        assert!(output.contains("pub afstemningstype: Option<Box<Afstemningstype>>,"));
+        assert!(output.contains("pub stemme: Vec<Stemme>,"));
+        assert!(!output.contains("pub afstemningstype: Afstemningstype,"));
+    }
+
+    #[test]
+    fn test_reflected_entity_set() {
+        let output = generate_northwind(GeneratorOptions::default());
+
+        assert!(output.contains("fn entity_set() -> Option<&'static str>;"));
+        assert!(output.contains(
+            "fn name() -> &'static str {\n            \"Order\"\n        }\n\n        \
+             fn entity_set() -> Option<&'static str> {\n            Some(\"Orders\")\n        }"
+        ));
+
+        let source = std::fs::read_to_string("tests/northwind.xml")
+            .unwrap()
+            .replace(
+                "<EntitySet Name=\"Orders\" EntityType=\"NorthwindModel.Order\"/>",
+                "",
+            );
+        let output = generate(
+            &Edmx::from_str(&source).unwrap(),
+            &GeneratorOptions::default(),
+        );
+        assert!(output.contains(
+            "fn name() -> &'static str {\n            \"Order\"\n        }\n\n        \
+             fn entity_set() -> Option<&'static str> {\n            None\n        }"
+        ));
+    }
+
+    #[test]
+    fn test_colliding_entity_set_imports_are_skipped() {
+        let project = Edmx::from_str(indoc! {r#"
+            <edmx:Edmx Version="1.0" xmlns:edmx="http://schemas.microsoft.com/ado/2007/06/edmx">
+              <edmx:DataServices>
+                <Schema Namespace="Sales">
+                  <EntityType Name="Order">
+                    <Key><PropertyRef Name="Id"/></Key>
+                    <Property Name="Id" Type="Edm.Int32" Nullable="false"/>
+                  </EntityType>
+                </Schema>
+                <Schema Namespace="Purchasing">
+                  <EntityType Name="Order">
+                    <Key><PropertyRef Name="Id"/></Key>
+                    <Property Name="Id" Type="Edm.Int32" Nullable="false"/>
+                  </EntityType>
+                </Schema>
+                <Schema Namespace="Default">
+                  <EntityType Name="Invoice">
+                    <Key><PropertyRef Name="Id"/></Key>
+                    <Property Name="Id" Type="Edm.Int32" Nullable="false"/>
+                  </EntityType>
+                  <EntityContainer Name="Container">
+                    <EntitySet Name="SalesOrders" EntityType="Sales.Order"/>
+                    <EntitySet Name="PurchaseOrders" EntityType="Purchasing.Order"/>
+                    <EntitySet Name="Invoices" EntityType="Default.Invoice"/>
+                  </EntityContainer>
+                </Schema>
+              </edmx:DataServices>
+            </edmx:Edmx>
+        "#})
+        .unwrap();
+
+        // The first set in document order wins
+        let (output, warnings) = generate_with_warnings(
+            &project,
+            &GeneratorOptions {
+                unsorted: true,
+                ..Default::default()
+            },
+        );
+
+        assert!(output.contains("pub use crate::sales::Order;"));
+        assert!(!output.contains("pub use crate::purchasing::Order;"));
+        assert!(!output.contains("pub use crate::default::Invoice;"));
+        assert!(output.contains("pub use default::*;"));
+        assert_eq!(
+            warnings,
+            vec!["not re-exporting crate::purchasing::Order from crate::default, as Order already refers to crate::sales::Order"]
+        );
+    }
+
+    #[test]
+    fn test_serde_mode_selects_derives() {
+        for (mode, derives) in [
+            (None, "serde::Serialize, serde::Deserialize"),
+            (Some("both"), "serde::Serialize, serde::Deserialize"),
+            (Some("de"), "serde::Deserialize"),
+            (Some("ser"), "serde::Serialize"),
+        ] {
+            let output = generate_northwind(GeneratorOptions {
+                serde_mode: mode.map(str::to_string),
+                ..Default::default()
+            });
+
+            assert_eq!(
+                output
+                    .matches(&format!(
+                        "#[cfg_attr(feature = \"serde\", derive({}))]\n    pub struct",
+                        derives
+                    ))
+                    .count(),
+                4
+            );
+        }
+    }
+
+    #[test]
+    fn test_skip_serializing_keys() {
+        let output = generate_northwind(GeneratorOptions {
+            serde_mode: Some("ser".to_string()),
+            skip_serializing_keys: true,
+            ..Default::default()
+        });
+
+        assert!(output.contains(
+            "#[cfg_attr(feature = \"serde\", serde(skip_serializing))]\n        \
+             #[cfg_attr(feature = \"serde\", serde(rename = \"CategoryID\"))]\n        \
+             pub categoryid: i32,"
+        ));
+        assert_eq!(output.matches("serde(skip_serializing)").count(), 4);
+
+        let output = generate_northwind(GeneratorOptions {
+            serde_mode: Some("de".to_string()),
+            skip_serializing_keys: true,
+            ..Default::default()
+        });
+
+        assert!(!output.contains("serde(skip_serializing)"));
+    }
+
+    #[test]
+    fn test_skip_none() {
+        let output = generate_northwind(GeneratorOptions {
+            skip_none: true,
+            ..Default::default()
+        });
+
+        assert!(output.contains(
+            "#[cfg_attr(feature = \"serde\", serde(default))]\n        \
+             #[cfg_attr(feature = \"serde\", serde(skip_serializing_if = \"Option::is_none\"))]\n        \
+             #[cfg_attr(feature = \"serde\", serde(deserialize_with = \"crate::empty_string_as_none\"))]\n        \
+             #[cfg_attr(feature = \"serde\", serde(rename = \"ContactName\"))]\n        \
+             pub contactname: Option<String>,"
+        ));
+        assert!(output.contains(
+            "#[cfg_attr(feature = \"serde\", serde(rename = \"Customer\", default))]\n        \
+             #[cfg_attr(feature = \"serde\", serde(skip_serializing_if = \"Option::is_none\"))]\n        \
+             pub customer: Option<Box<Customer>>,"
+        ));
+        // Required properties and collections are always serialized.
+        assert!(!output.contains(
+            "serde(skip_serializing_if = \"Option::is_none\"))]\n        \
+             #[cfg_attr(feature = \"serde\", serde(rename = \"CustomerID\"))]"
+        ));
+
+        let output = generate_northwind(GeneratorOptions {
+            skip_none: true,
+            serde_mode: Some("de".to_string()),
+            ..Default::default()
+        });
+        assert!(!output.contains("skip_serializing_if"));
+    }
+
+    #[test]
+    fn test_optional_properties_default_to_none() {
+        let output = generate_northwind(GeneratorOptions::default());
+
+        assert!(output.contains(
+            "#[cfg_attr(feature = \"serde\", serde(default))]\n        \
+             #[cfg_attr(feature = \"serde\", serde(deserialize_with = \"crate::empty_string_as_none\"))]\n        \
+             #[cfg_attr(feature = \"serde\", serde(rename = \"ContactName\"))]\n        \
+             pub contactname: Option<String>,"
+        ));
+        assert!(output.contains(
+            "#[cfg_attr(feature = \"serde\", serde(default))]\n        \
+             #[cfg_attr(feature = \"serde\", serde(rename = \"UnitsInStock\"))]\n        \
+             pub unitsinstock: Option<i16>,"
+        ));
+        assert!(output.contains(
+            "#[cfg_attr(feature = \"serde\", serde(rename = \"CustomerID\"))]\n        \
+             pub customerid: String,"
+        ));
+    }
+
+    #[test]
+    fn test_generate_to_out_dir() {
+        let out_dir = std::env::temp_dir().join("odata-rust-generator-out-dir");
+        std::fs::create_dir_all(&out_dir).unwrap();
+        std::env::set_var("OUT_DIR", &out_dir);
+
+        let output_file = generate_to_out_dir(
+            "tests/northwind.xml",
+            "northwind.rs",
+            &GeneratorOptions::default(),
+        );
+
+        assert_eq!(output_file, out_dir.join("northwind.rs"));
+        assert_eq!(
+            std::fs::read_to_string(output_file).unwrap(),
+            generate_northwind(GeneratorOptions::default())
+        );
+    }
+
+    #[test]
+    fn test_get_field_by_reflected_name() {
+        let output = generate_northwind(GeneratorOptions::default());
+
+        assert!(
+            output.contains("pub enum OpenDataValue<'a> {\n    Null,\n    Binary(&'a Vec<u8>),")
+        );
+        assert!(output.contains(
+            "fn get_field(&self, name: &str) -> Option<crate::OpenDataValue<'_>> {\n            \
+             Some(match name {\n            \
+             \t\"CustomerID\" => crate::OpenDataValue::String(&self.customerid),"
+        ));
+        assert!(output.contains(
+            "\t\"Phone\" => match &self.phone {\n            \
+             \t\tSome(value) => crate::OpenDataValue::String(value),\n            \
+             \t\t_ => crate::OpenDataValue::Null,\n            \
+             \t},"
+        ));
+    }
+
+    #[test]
+    fn test_navigations_resolve_across_schemas() {
+        let project = Edmx::from_str(indoc! {r#"
+            <edmx:Edmx Version="1.0" xmlns:edmx="http://schemas.microsoft.com/ado/2007/06/edmx">
+              <edmx:DataServices>
+                <Schema Namespace="Sales">
+                  <EntityType Name="Order">
+                    <Key><PropertyRef Name="Id"/></Key>
+                    <Property Name="Id" Type="Edm.Int32" Nullable="false"/>
+                    <NavigationProperty Name="Customer" Relationship="Relations.Order_Customer" ToRole="Customer" FromRole="Order"/>
+                    <NavigationProperty Name="Invoice" Relationship="Relations.Order_Invoice" ToRole="Invoice" FromRole="Order"/>
+                  </EntityType>
+                </Schema>
+                <Schema Namespace="Relations">
+                  <EntityType Name="Customer">
+                    <Key><PropertyRef Name="Id"/></Key>
+                    <Property Name="Id" Type="Edm.Int32" Nullable="false"/>
+                  </EntityType>
+                  <Association Name="Order_Customer">
+                    <End Type="Relations.Customer" Role="Customer" Multiplicity="0..1"/>
+                    <End Type="Sales.Order" Role="Order" Multiplicity="*"/>
+                  </Association>
+                </Schema>
+              </edmx:DataServices>
+            </edmx:Edmx>
+        "#})
+        .unwrap();
+
+        let (output, warnings) = generate_with_warnings(&project, &GeneratorOptions::default());
+
+        assert_eq!(
+            warnings,
+            vec!["could not resolve the target of navigation Sales.Order.Invoice through Relations.Order_Invoice, generating it as serde_json::Value"]
+        );
+        assert!(output.contains(
+            "/// See [`crate::relations::Customer`]\n        \
+             #[cfg_attr(feature = \"serde\", serde(rename = \"Customer\", default))]\n        \
+             pub customer: Option<Box<crate::relations::Customer>>,"
+        ));
+        assert!(output.contains("pub invoice: serde_json::Value,"));
+        assert!(output.contains("&[(\"Customer\", \"Customer\", \"optional\")]"));
+    }
+
+    #[test]
+    fn test_wire_names_are_escaped() {
+        let project = Edmx::from_str(indoc! {r#"
+            <edmx:Edmx Version="1.0" xmlns:edmx="http://schemas.microsoft.com/ado/2007/06/edmx">
+              <edmx:DataServices>
+                <Schema Namespace="Sales">
+                  <EntityType Name="Order">
+                    <Key><PropertyRef Name="Id"/></Key>
+                    <Property Name="Id" Type="Edm.Int32" Nullable="false"/>
+                    <Property Name="Note&quot;s" Type="Edm.String"/>
+                    <Property Name="Path\Name" Type="Edm.String"/>
+                  </EntityType>
+                </Schema>
+              </edmx:DataServices>
+            </edmx:Edmx>
+        "#})
+        .unwrap();
+
+        let output = generate(
+            &project,
+            &GeneratorOptions {
+                field_case: Some("snake".to_string()),
+                ..Default::default()
+            },
+        );
+
+        assert!(output.contains("serde(rename = \"Note\\\"s\"))]"));
+        assert!(output.contains("serde(rename = \"Path\\\\Name\"))]"));
+        assert!(output.contains("(\"Note\\\"s\", crate::OpenDataType::String {"));
+        assert!(output.contains("\"Path\\\\Name\" => match &self."));
+    }
+
+    #[test]
+    fn test_check_reports_problems() {
+        let project = Edmx::from_str(indoc! {r#"
+            <edmx:Edmx Version="1.0" xmlns:edmx="http://schemas.microsoft.com/ado/2007/06/edmx">
+              <edmx:DataServices>
+                <Schema Namespace="Sales">
+                  <EntityType Name="Order">
+                    <Key><PropertyRef Name="Id"/></Key>
+                    <Property Name="Id" Type="Edm.Int32" Nullable="false"/>
+                    <Property Name="Type" Type="Edm.String"/>
+                    <Property Name="Match" Type="Edm.String"/>
+                    <Property Name="Ship-Date" Type="Edm.DateTime"/>
+                    <NavigationProperty Name="Customer" Relationship="Sales.Order_Customer" ToRole="Customer" FromRole="Order"/>
+                  </EntityType>
+                  <EntityType Name="match">
+                    <Key><PropertyRef Name="Id"/></Key>
+                    <Property Name="Id" Type="Edm.Int32" Nullable="false"/>
+                  </EntityType>
+                </Schema>
+              </edmx:DataServices>
+            </edmx:Edmx>
+        "#})
+        .unwrap();
+
+        assert_eq!(
+            check(&project),
+            vec![
+                "property Sales.Order.Ship-Date is not a valid Rust identifier",
+                "navigation Sales.Order.Customer can't be resolved through Sales.Order_Customer to role Customer",
+                "entity type Sales.match is a reserved Rust keyword",
+            ]
+        );
+
+        let source = std::fs::read_to_string("tests/northwind.xml").unwrap();
+        assert!(check(&Edmx::from_str(&source).unwrap()).is_empty());
+    }
+
+    #[test]
+    fn test_patch_structs() {
+        let output = generate_northwind(GeneratorOptions {
+            patch_structs: true,
+            ..Default::default()
+        });
+
+        assert!(output.contains(
+            "#[derive(Debug, Default)]\n    \
+             #[cfg_attr(feature = \"serde\", derive(serde::Serialize, serde::Deserialize))]\n    \
+             pub struct CustomerPatch {\n        \
+             #[cfg_attr(feature = \"serde\", serde(default, skip_serializing_if = \"Option::is_none\"))]\n        \
+             #[cfg_attr(feature = \"serde\", serde(rename = \"CustomerID\"))]\n        \
+             pub customerid: Option<String>,"
+        ));
+        assert!(output.contains("pub phone: Option<Option<String>>,"));
+        assert!(output.contains(
+            "pub fn companyname(self, value: String) -> Self {\n            \
+             Self { companyname: Some(value), ..self }"
+        ));
+        assert!(!output.contains("pub orders: Option<"));
+    }
+
+    #[test]
+    fn test_projections() {
+        let output = generate_northwind(GeneratorOptions {
+            projections: vec![
+                (
+                    "NorthwindModel.Customer".to_string(),
+                    vec!["ContactName".to_string(), "CustomerID".to_string()],
+                ),
+                ("Order".to_string(), vec!["Freight".to_string()]),
+            ]
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        });
+
+        assert!(output.contains(
+            "/// Customer with only the properties requested by `CustomerProjection::SELECT`.\n    \
+             #[derive(Debug)]\n    \
+             #[cfg_attr(feature = \"serde\", derive(serde::Serialize, serde::Deserialize))]\n    \
+             pub struct CustomerProjection {\n        \
+             #[cfg_attr(feature = \"serde\", serde(rename = \"CustomerID\"))]\n        \
+             pub customerid: String,\n        \
+             #[cfg_attr(feature = \"serde\", serde(default))]\n        \
+             #[cfg_attr(feature = \"serde\", serde(deserialize_with = \"crate::empty_string_as_none\"))]\n        \
+             #[cfg_attr(feature = \"serde\", serde(rename = \"ContactName\"))]\n        \
+             pub contactname: Option<String>,\n    \
+             }"
+        ));
+        assert!(output.contains("pub const SELECT: &'static str = \"CustomerID,ContactName\";"));
+        assert!(output.contains("pub struct OrderProjection {"));
+        assert!(!output.contains("pub struct ProductProjection"));
+    }
+
+    #[test]
+    fn test_check_options() {
+        let source = std::fs::read_to_string("tests/northwind.xml").unwrap();
+        let project = Edmx::from_str(&source).unwrap();
+        let projections = |properties: &[&str]| GeneratorOptions {
+            projections: vec![(
+                "Customer".to_string(),
+                properties
+                    .iter()
+                    .map(|property| property.to_string())
+                    .collect(),
+            )]
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        };
+
+        assert!(check_options(&project, &projections(&["CompanyName"])).is_empty());
+        assert_eq!(
+            check_options(
+                &project,
+                &projections(&["CompanyName", "companyname", "Orders"])
+            ),
+            vec![
+                "projection of NorthwindModel.Customer selects unknown property companyname",
+                "projection of NorthwindModel.Customer selects unknown property Orders",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_parsed_metadata() {
+        let output = generate_northwind(GeneratorOptions {
+            validate: true,
+            ..Default::default()
+        });
+
+        // MaxLength and Precision are in the document, but the parser leaves them out, so every
+        // entity validates successfully.
+        assert_eq!(
+            output
+                .matches(
+                    "pub fn validate(&self) -> Result<(), Vec<crate::ValidationError>> {\n            \
+                     Ok(())"
+                )
+                .count(),
+            4
+        );
+        assert!(!output.contains("errors.push"));
+    }
+
+    #[test]
+    fn test_validate() {
+        // The parser doesn't read facets from documents yet, so they are filled in by hand.
+        let source = std::fs::read_to_string("tests/northwind.xml").unwrap();
+        let mut project = Edmx::from_str(&source).unwrap();
+        for entity in &mut project.data_services.schemas[0].entities {
+            for property in &mut entity.properties {
+                match (&mut property.inner, property.name.as_str()) {
+                    (PropertyType::String { max_length, .. }, "CustomerID") => {
+                        *max_length = Some(5)
+                    }
+                    (PropertyType::String { max_length, .. }, "CompanyName") => {
+                        *max_length = Some(40)
+                    }
+                    (PropertyType::Decimal { precision, .. }, "Freight") => *precision = Some(19),
+                    _ => {}
+                }
+            }
+        }
+
+        let output = generate(
+            &project,
+            &GeneratorOptions {
+                validate: true,
+                ..Default::default()
+            },
+        );
+        assert!(output.contains("pub enum ValidationError {"));
+        assert!(output.contains(
+            "pub fn validate(&self) -> Result<(), Vec<crate::ValidationError>> {\n            \
+             let mut errors = Vec::new();\n            \
+             if self.customerid.chars().count() > 5 {\n            \
+             \terrors.push(crate::ValidationError::TooLong { property: \"CustomerID\", max_length: 5, length: self.customerid.chars().count() });\n            \
+             }\n            \
+             if self.companyname.chars().count() > 40 {"
+        ));
+        assert!(output.contains(
+            "if let Some(value) = &self.customerid {\n            \
+             \tif value.chars().count() > 5 {"
+        ));
+        assert!(output.contains(
+            "if let Some(value) = &self.freight {\n            \
+             \tif value.abs() >= 1e19 {\n            \
+             \t\terrors.push(crate::ValidationError::TooPrecise { property: \"Freight\", precision: 19 });"
+        ));
+        // Products have no facets to check.
+        assert!(output.contains(
+            "pub fn validate(&self) -> Result<(), Vec<crate::ValidationError>> {\n            \
+             Ok(())"
+        ));
+
+        let output = generate(
+            &project,
+            &GeneratorOptions {
+                validate: true,
+                decimal_type: Some("i128".to_string()),
+                typed_ids: true,
+                ..Default::default()
+            },
+        );
+        assert!(output.contains("if value.unsigned_abs() >= 10u128.pow(19) {"));
+        assert!(output.contains("if self.customerid.0.chars().count() > 5 {"));
+
+        assert!(!generate(&project, &GeneratorOptions::default()).contains("fn validate"));
+    }
+
+    #[test]
+    fn test_nullable_keys_are_required() {
+        let project = Edmx::from_str(indoc! {r#"
+            <edmx:Edmx Version="1.0" xmlns:edmx="http://schemas.microsoft.com/ado/2007/06/edmx">
+              <edmx:DataServices>
+                <Schema Namespace="Sales">
+                  <EntityType Name="Order">
+                    <Key><PropertyRef Name="Id"/></Key>
+                    <Property Name="Id" Type="Edm.Int32"/>
+                    <Property Name="Note" Type="Edm.String"/>
+                  </EntityType>
+                </Schema>
+              </edmx:DataServices>
+            </edmx:Edmx>
+        "#})
+        .unwrap();
+
+        let output = generate(
+            &project,
+            &GeneratorOptions {
+                key_structs: true,
+                ..Default::default()
+            },
+        );
+
+        assert!(output.contains("pub id: i32,"));
+        assert!(output.contains("pub note: Option<String>,"));
+        assert!(
+            output.contains("(\"Id\", crate::OpenDataType::Int32 { nullable: false, key: true })")
+        );
+        assert!(
+            output.contains("impl OrderKey {") || output.contains("pub fn id(&self) -> OrderKey")
+        );
+    }
+
+    #[test]
+    fn test_field_optionality_matches_reflection() {
+        let project = Edmx::from_str(indoc! {r#"
+            <edmx:Edmx Version="1.0" xmlns:edmx="http://schemas.microsoft.com/ado/2007/06/edmx">
+              <edmx:DataServices>
+                <Schema Namespace="Sales">
+                  <EntityType Name="Order">
+                    <Key><PropertyRef Name="Id"/></Key>
+                    <Property Name="Id" Type="Edm.Int32" Nullable="true"/>
+                    <Property Name="Quantity" Type="Edm.Int32" Nullable="false"/>
+                    <Property Name="Discount" Type="Edm.Int32" Nullable="true"/>
+                    <Property Name="Note" Type="Edm.String"/>
+                  </EntityType>
+                  <EntityType Name="Tag">
+                    <Key><PropertyRef Name="Name"/></Key>
+                    <Property Name="Name" Type="Edm.String"/>
+                  </EntityType>
+                </Schema>
+              </edmx:DataServices>
+            </edmx:Edmx>
+        "#})
+        .unwrap();
+
+        let cases = vec![
+            ("id", "Id", "Int32", false),
+            ("quantity", "Quantity", "Int32", false),
+            ("discount", "Discount", "Int32", true),
+            ("note", "Note", "String", true),
+            ("name", "Name", "String", false),
+        ];
+
+        for typed_ids in [false, true] {
+            let output = generate(
+                &project,
+                &GeneratorOptions {
+                    typed_ids,
+                    ..Default::default()
+                },
+            );
+
+            for (field, property, variant, nullable) in &cases {
+                let key = *field == "id" || *field == "name";
+                let field_type = output
+                    .lines()
+                    .find_map(|line| line.trim().strip_prefix(&format!("pub {}: ", field)))
+                    .unwrap();
+
+                assert_eq!(field_type.starts_with("Option<"), *nullable, "{}", field);
+                assert!(output.contains(&format!(
+                    "(\"{}\", crate::OpenDataType::{} {{ nullable: {}, key: {}",
+                    property, variant, nullable, key
+                )));
+            }
+        }
+    }
+
+    #[test]
+    fn test_entity_set_name_trait() {
+        let output = generate_northwind(GeneratorOptions::default());
+
+        assert!(output.contains("pub trait EntitySetName {\n    const SET_NAME: &'static str;\n}"));
+        assert!(output.contains(
+            "impl crate::EntitySetName for Customer {\n        \
+             const SET_NAME: &'static str = \"Customers\";\n    \
+             }"
+        ));
+        assert_eq!(output.matches("crate::EntitySetName for").count(), 4);
+    }
+
+    #[test]
+    fn test_non_exhaustive_structs() {
+        let output = generate_northwind(GeneratorOptions {
+            non_exhaustive: true,
+            patch_structs: true,
+            ..Default::default()
+        });
+
+        assert!(output.contains("#[derive(Debug)]\n    #[non_exhaustive]\n    #[cfg_attr(feature = \"serde\", derive(serde::Serialize, serde::Deserialize))]\n    pub struct Customer {"));
+        assert!(output.contains("#[derive(Debug, Default)]\n    #[non_exhaustive]\n    #[cfg_attr(feature = \"serde\", derive(serde::Serialize, serde::Deserialize))]\n    pub struct CustomerPatch {"));
+        assert!(output.contains("pub fn phone(self, value: Option<String>) -> Self {"));
+        assert_eq!(output.matches("#[non_exhaustive]").count(), 8);
+
+        let output = generate_northwind(GeneratorOptions::default());
+        assert!(!output.contains("#[non_exhaustive]"));
+    }
+
+    #[test]
+    fn test_lenient_numbers() {
+        let output = generate_northwind(GeneratorOptions {
+            lenient_numbers: true,
+            ..Default::default()
+        });
+
+        assert!(output.contains("enum LenientNumber<T> {\n    Number(T),\n    Str(String),\n}"));
+        assert!(output.contains(
+            "#[cfg_attr(feature = \"serde\", serde(deserialize_with = \"crate::lenient_number\"))]\n        \
+             #[cfg_attr(feature = \"serde\", serde(rename = \"OrderID\"))]\n        \
+             pub orderid: i32,"
+        ));
+        assert!(output.contains(
+            "#[cfg_attr(feature = \"serde\", serde(deserialize_with = \"crate::lenient_optional_number\"))]\n        \
+             #[cfg_attr(feature = \"serde\", serde(rename = \"Freight\"))]\n        \
+             pub freight: Option<f64>,"
+        ));
+        assert_eq!(
+            output
+                .matches("serde(deserialize_with = \"crate::lenient_")
+                .count(),
+            7
+        );
+    }
+
+    #[test]
+    fn test_datetime_type() {
+        for (datetime_type, rust_type) in [
+            (None, "chrono::NaiveDateTime"),
+            (Some("chrono-naive"), "chrono::NaiveDateTime"),
+            (Some("chrono-utc"), "chrono::DateTime<chrono::Utc>"),
+            (Some("time"), "time::OffsetDateTime"),
+        ] {
+            let output = generate_northwind(GeneratorOptions {
+                datetime_type: datetime_type.map(str::to_string),
+                ..Default::default()
+            });
+
+            assert!(output.contains(&format!("pub orderdate: Option<{}>,", rust_type)));
+            assert!(output.contains(&format!("DateTime(&'a {}),", rust_type)));
+            assert!(output.contains("crate::OpenDataType::DateTime { nullable: true, key: false }"));
+        }
+
+        let output = generate_northwind(GeneratorOptions {
+            datetime_type: Some("time".to_string()),
+            ..Default::default()
+        });
+        assert!(output.contains(
+            "#[cfg_attr(feature = \"serde\", serde(with = \"time::serde::rfc3339::option\"))]"
+        ));
+    }
+
+    #[test]
+    fn test_decimal_type() {
+        for (decimal_type, rust_type) in [
+            (None, "f64"),
+            (Some("f64"), "f64"),
+            (Some("i128"), "i128"),
+            (Some("rust-decimal"), "rust_decimal::Decimal"),
+        ] {
+            let output = generate_northwind(GeneratorOptions {
+                decimal_type: decimal_type.map(str::to_string),
+                ..Default::default()
+            });
+
+            assert!(output.contains(&format!("pub freight: Option<{}>,", rust_type)));
+            assert!(output.contains(&format!("Decimal(&'a {}),", rust_type)));
+        }
+
+        let output = generate_northwind(GeneratorOptions {
+            decimal_type: Some("i128".to_string()),
+            decimal_scale: Some(2),
+            lenient_numbers: true,
+            ..Default::default()
+        });
+        assert!(output.contains("const DECIMAL_SCALE: usize = 2;"));
+        assert!(output.contains(
+            "#[cfg_attr(feature = \"serde\", serde(serialize_with = \"crate::serialize_optional_scaled_decimal\", deserialize_with = \"crate::deserialize_optional_scaled_decimal\"))]\n        \
+             #[cfg_attr(feature = \"serde\", serde(rename = \"Freight\"))]\n        \
+             pub freight: Option<i128>,"
+        ));
+        assert!(output.contains("fn parse_scaled_decimal(text: &str) -> Option<i128> {"));
+        assert!(!output.contains("fn deserialize_scaled_decimal"));
+        assert!(!output.contains(
+            "lenient_optional_number\"))]\n        \
+             #[cfg_attr(feature = \"serde\", serde(serialize_with = \"crate::serialize_optional_scaled_decimal\""
+        ));
+        // f64 decimals need no helpers.
+        assert!(!generate_northwind(GeneratorOptions::default()).contains("scaled_decimal"));
+    }
+
+    #[test]
+    fn test_any_entity() {
+        let output = generate_northwind(GeneratorOptions {
+            any_entity: true,
+            ..Default::default()
+        });
+
+        assert!(output.contains(
+            "#[derive(Debug)]\n    \
+             #[cfg_attr(feature = \"serde\", derive(serde::Serialize, serde::Deserialize))]\n    \
+             #[cfg_attr(feature = \"serde\", serde(untagged))]\n    \
+             pub enum AnyEntity {\n        \
+             Category(Category),\n        \
+             Customer(Customer),\n        \
+             Order(Order),\n        \
+             Product(Product),\n    \
+             }"
+        ));
+        assert!(output.contains(
+            "impl From<Order> for AnyEntity {\n        \
+             fn from(entity: Order) -> Self {\n            \
+             AnyEntity::Order(entity)\n        \
+             }"
+        ));
+        assert!(!generate_northwind(GeneratorOptions::default()).contains("AnyEntity"));
+    }
+
+    #[test]
+    fn test_lowercase_collisions_are_disambiguated() {
+        let project = Edmx::from_str(indoc! {r#"
+            <edmx:Edmx Version="1.0" xmlns:edmx="http://schemas.microsoft.com/ado/2007/06/edmx">
+              <edmx:DataServices>
+                <Schema Namespace="Sales">
+                  <EntityType Name="Order">
+                    <Key><PropertyRef Name="ID"/></Key>
+                    <Property Name="ID" Type="Edm.Int32" Nullable="false"/>
+                    <Property Name="Id" Type="Edm.Int32" Nullable="false"/>
+                    <Property Name="Type" Type="Edm.String" Nullable="false"/>
+                  </EntityType>
+                </Schema>
+              </edmx:DataServices>
+            </edmx:Edmx>
+        "#})
+        .unwrap();
+
+        let output = generate(
+            &project,
+            &GeneratorOptions {
+                rename_all: true,
+                ..Default::default()
+            },
+        );
+
+        assert!(output.contains(
+            "#[cfg_attr(feature = \"serde\", serde(rename_all = \"UPPERCASE\"))]\n    \
+             pub struct Order {\n        \
+             pub id: i32,\n        \
+             #[cfg_attr(feature = \"serde\", serde(rename = \"Id\"))]\n        \
+             pub id_2: i32,\n        \
+             #[cfg_attr(feature = \"serde\", serde(rename = \"Type\"))]\n        \
+             pub r#type: String,"
+        ));
+        assert!(output.contains("\"Id\" => crate::OpenDataValue::Int32(&self.id_2),"));
+    }
+
+    #[test]
+    fn test_field_case() {
+        let output = generate_northwind(GeneratorOptions {
+            field_case: Some("snake".to_string()),
+            ..Default::default()
+        });
+        assert!(output.contains(
+            "#[cfg_attr(feature = \"serde\", serde(rename = \"CustomerID\"))]\n        \
+             pub customer_id: String,"
+        ));
+        assert!(output.contains(
+            "#[cfg_attr(feature = \"serde\", serde(rename = \"UnitsInStock\"))]\n        \
+             pub units_in_stock: Option<i16>,"
+        ));
+
+        let output = generate_northwind(GeneratorOptions {
+            field_case: Some("snake".to_string()),
+            rename_all: true,
+            ..Default::default()
+        });
+        assert!(output.contains("serde(rename_all = \"PascalCase\")"));
+        assert!(output.contains(
+            "#[cfg_attr(feature = \"serde\", serde(rename = \"CustomerID\"))]\n        \
+             pub customer_id: String,"
+        ));
+        assert!(output.contains("\n        pub units_in_stock: Option<i16>,"));
+
+        let output = generate_northwind(GeneratorOptions {
+            field_case: Some("original".to_string()),
+            ..Default::default()
+        });
+        assert!(output.contains("#[allow(non_snake_case)]"));
+        assert!(output.contains("\n        pub CustomerID: String,"));
+        assert!(!output.contains("serde(rename = \"CustomerID\")"));
+
+        let output = generate_northwind(GeneratorOptions {
+            field_case: Some("lower".to_string()),
+            ..Default::default()
+        });
+        assert!(output.contains(
+            "#[cfg_attr(feature = \"serde\", serde(rename = \"CustomerID\"))]\n        \
+             pub customerid: String,"
+        ));
+    }
+
+    #[test]
+    fn test_custom_header() {
+        let output = generate_northwind(GeneratorOptions {
+            header: Some("// Copyright Example Corp.\n".to_string()),
+            ..Default::default()
+        });
+        assert!(output.starts_with("// Copyright Example Corp.\n\npub use default::*;"));
+        assert!(!output.contains("// Code automatically generated using"));
+
+        let output = generate_northwind(GeneratorOptions {
+            no_header: true,
+            ..Default::default()
+        });
+        assert!(!output.starts_with("//"));
+        assert!(!output.contains("// Code automatically generated using"));
+    }
+}
@@ -1,22 +1,26 @@
-#![feature(pattern)]
-
 use clap::Parser;
-use codegen::{Field, Function, Scope, Trait};
 use indoc::indoc;
-use odata_parser_rs::{Edmx, EntityType, NavigationProperty, Property, PropertyType, Schema};
+use odata_parser_rs::Edmx;
+use odata_rust_generator::{
+    cargo_features, cargo_manifest, check, check_options, generate_with_warnings, merge_projects,
+    write_if_changed, GeneratorOptions, TypeOverride,
+};
 use std::{
-    collections::VecDeque,
-    path::PathBuf,
-    str::{pattern::Pattern, FromStr},
+    io::Read,
+    path::{Path, PathBuf},
+    str::FromStr,
 };
 
-#[derive(Parser)]
+#[derive(Parser, Default)]
 #[clap(long_about = indoc! {"
     Command-line utility for generating Rust code from OData metadata.xml documents
 "})]
 struct Opts {
-    #[clap(about = "Path to metadata.xml file to generate code from")]
-    pub input_file: PathBuf,
+    #[clap(
+        about = "Paths to metadata.xml files to generate code from, merging their schemas. Use - to read from stdin, or an http(s):// URL to fetch it (requires the \"http\" feature). With --output-dir, directories and file name patterns such as services/*.xml are also accepted",
+        required = true
+    )]
+    pub input_file: Vec<PathBuf>,
     #[clap(
         long,
         about = "Don't derive Serialize and Deserialize traits to all structs"
@@ -41,359 +45,766 @@ struct Opts {
     )]
     pub no_expand: bool,
 
+    #[clap(
+        long,
+        about = "Put a single serde(rename_all) on each struct using the detected naming convention, only renaming fields which don't follow it"
+    )]
+    pub rename_all: bool,
+
+    #[clap(
+        long,
+        about = "Also coerce empty strings into None when deserializing nullable non-string properties, such as numbers and dates"
+    )]
+    pub empty_values_are_null: bool,
+
+    #[clap(
+        long,
+        about = "Accept \"true\"/\"false\" strings and 0/1 integers when deserializing Boolean properties"
+    )]
+    pub lenient_bools: bool,
+
+    #[clap(
+        long,
+        about = "Accept numbers encoded as strings when deserializing numeric properties"
+    )]
+    pub lenient_numbers: bool,
+
+    #[clap(
+        long,
+        possible_values = &["original", "snake", "lower"],
+        about = "How property names are turned into field names. Defaults to lower"
+    )]
+    pub field_case: Option<String>,
+
+    #[clap(
+        long,
+        about = "Generate an AnyEntity enum in each schema with a variant for every entity type"
+    )]
+    pub any_entity: bool,
+
+    #[clap(
+        long,
+        possible_values = &["chrono-naive", "chrono-utc", "time"],
+        about = "Rust type generated for DateTime properties. Defaults to chrono-naive"
+    )]
+    pub datetime_type: Option<String>,
+
+    #[clap(
+        long = "decimal-as",
+        possible_values = &["f64", "i128", "rust-decimal"],
+        about = "Rust type generated for Decimal properties. Defaults to f64. i128 holds the value multiplied by 10^scale, rounding further digits half away from zero"
+    )]
+    pub decimal_type: Option<String>,
+
+    #[clap(
+        long,
+        about = "Number of fractional digits kept by --decimal-as i128. Defaults to 0"
+    )]
+    pub decimal_scale: Option<u32>,
+
+    #[clap(
+        long,
+        about = "Generate a <Entity>Key struct for each entity, along with a conversion from the entity"
+    )]
+    pub key_structs: bool,
+
+    #[clap(
+        long,
+        about = "Generate collection_url and url functions on each entity in an entity set, building OData URLs for the set and for a single entity by key. Implies --key-structs"
+    )]
+    pub url_builders: bool,
+
+    #[clap(
+        long,
+        about = "Generate a test module in each schema, checking that default-constructed entities survive a round trip through serde_json. Entities derive PartialEq in tests, so types given with --type-override must implement it"
+    )]
+    pub emit_tests: bool,
+
+    #[clap(
+        long,
+        about = "Generate navigations to a single required entity as Box<T> rather than Option<Box<T>>. Such navigations must then always be $expand-ed"
+    )]
+    pub required_navigations: bool,
+
+    #[clap(
+        long,
+        possible_values = &["box", "arc", "rc"],
+        about = "Pointer type wrapping navigated entities. Defaults to box. With arc and rc, collections hold pointers too, and serde's rc feature is required"
+    )]
+    pub nav_ptr: Option<String>,
+
+    #[clap(
+        long,
+        about = "Generate accessor methods for navigations, iterating collections and borrowing single related entities"
+    )]
+    pub nav_accessors: bool,
+
+    #[clap(
+        long,
+        about = "Generate a prelude module re-exporting the entity types, key structs and reflection traits of every schema"
+    )]
+    pub prelude: bool,
+
+    #[clap(
+        long,
+        about = "Implement Display on entities, showing the entity name and its key, e.g. Order(10248)"
+    )]
+    pub display: bool,
+
+    #[clap(
+        long,
+        about = "Wrap the key property of each entity in a <Entity>Id newtype, so keys of different entities can't be mixed up"
+    )]
+    pub typed_ids: bool,
+
+    #[clap(
+        long,
+        about = "Implement PartialEq on structs by comparing only their properties, ignoring NavigationProperties"
+    )]
+    pub eq_ignores_navigations: bool,
+
+    #[clap(
+        long,
+        about = "Generate a fuzz_deserialize function for exercising deserialization of every entity set, gated behind the \"fuzzing\" feature"
+    )]
+    pub fuzzing: bool,
+
+    #[clap(
+        long,
+        about = "Move NavigationProperties out of the entity structs and into separate <Entity>Expanded structs"
+    )]
+    pub group_navigations: bool,
+
+    #[clap(
+        long,
+        about = "Document each struct with the OData namespace and entity set it originates from"
+    )]
+    pub namespace_docs: bool,
+
+    #[clap(
+        long,
+        about = "Leading namespace (e.g. Com.Example) to drop from schema namespaces when generating module paths"
+    )]
+    pub strip_prefix: Option<String>,
+
+    #[clap(
+        long,
+        about = "Generate nullable properties as Patchable<T>, distinguishing missing properties from explicit nulls"
+    )]
+    pub triple_state_options: bool,
+
+    #[clap(
+        long,
+        about = "Generate ODataResponse<T> and ODataV2Response<T> types for deserializing collection responses"
+    )]
+    pub envelope: bool,
+
+    #[clap(
+        long,
+        about = "Generate a marker type for each entity set, implementing the EntitySet trait with the path, entity type and key type of the set"
+    )]
+    pub entity_set_markers: bool,
+
+    #[clap(
+        long,
+        about = "Generate a decode_collection function on each entity in an entity set, decoding v4 or v2 collection responses. Implies --envelope"
+    )]
+    pub decode_collections: bool,
+
+    #[clap(
+        long,
+        possible_values = &["pub", "pub(crate)"],
+        about = "Visibility of all generated modules, items, fields and re-exports. Defaults to pub"
+    )]
+    pub visibility: Option<String>,
+
+    #[clap(
+        long,
+        about = "Generate a select_<module path>_<entity>! macro for each entity, such as select_northwindmodel_order!, building a $select string from property names checked at compile time"
+    )]
+    pub select_macros: bool,
+
+    #[clap(long, about = "Sort the generated entity set re-exports")]
+    pub sort_imports: bool,
+
+    #[clap(
+        long,
+        about = "Keep schemas, entities, entity sets and reflection tables in document order instead of sorting them by name"
+    )]
+    pub unsorted: bool,
+
+    #[clap(
+        long,
+        about = "File containing a header comment to put at the top of the output instead of the default one"
+    )]
+    pub header: Option<PathBuf>,
+
+    #[clap(long, about = "Don't put a header comment at the top of the output")]
+    pub no_header: bool,
+
+    #[clap(
+        long,
+        about = "Emit an SPDX-License-Identifier line with the given license expression at the top of the output"
+    )]
+    pub spdx: Option<String>,
+
+    #[clap(
+        long,
+        about = "Allow the lints the selected options are known to trigger in the generated code, on the prefix module if there is one and on every item at the root of the output otherwise"
+    )]
+    pub emit_allow: bool,
+
+    #[clap(
+        long,
+        about = "Wrap all generated code in a single public module with the given name"
+    )]
+    pub module_prefix: Option<String>,
+
+    #[clap(
+        long,
+        about = "Don't re-export the contents of the default schema from the root through a glob import"
+    )]
+    pub no_glob_reexport: bool,
+
+    #[clap(
+        long,
+        about = "Put the items of the only schema directly at the root, instead of in a module named after its namespace. Has no effect when there are several schemas"
+    )]
+    pub flatten_single_schema: bool,
+
+    #[clap(
+        long,
+        possible_values = &["de", "ser", "both"],
+        about = "Which serde traits to derive on generated structs. Defaults to both"
+    )]
+    pub serde_mode: Option<String>,
+
+    #[clap(
+        long,
+        about = "Don't serialize key properties, for creating entities whose keys are assigned by the service"
+    )]
+    pub skip_serializing_keys: bool,
+
+    #[clap(
+        long,
+        about = "Don't serialize optional properties and navigations which are None, rather than sending them as null"
+    )]
+    pub skip_none: bool,
+
+    #[clap(
+        long,
+        about = "Generate a <Entity>Patch struct for each entity with every property optional, serializing only the properties which are set"
+    )]
+    pub patch_structs: bool,
+
+    #[clap(
+        long,
+        about = "Generate a <Entity>Projection struct with only some properties of an entity, for use with $select. Given as Entity:Property1,Property2, where the entity may be qualified by its namespace. Properties the entity doesn't have are rejected. Can be repeated"
+    )]
+    pub projection: Vec<String>,
+
+    #[clap(
+        long,
+        about = "Generate a validate method on each entity, checking strings and binary values against their MaxLength and decimals against their Precision. These facets aren't read from metadata documents yet, so until they are every validate method returns Ok(())"
+    )]
+    pub validate: bool,
+
+    #[clap(
+        long,
+        about = "Mark entity, <Entity>Expanded and <Entity>Patch structs as #[non_exhaustive], so other crates can't construct them with struct literals"
+    )]
+    pub non_exhaustive: bool,
+
+    #[clap(
+        long,
+        about = "Add a to_json method to OpenDataModel, serializing entities into a serde_json::Value keyed by the OData property names. Has no effect with --no-reflection or --serde-mode de"
+    )]
+    pub to_json: bool,
+
+    #[clap(
+        long,
+        about = "Also emit the Cargo [features] and [dependencies] needed by the generated code. They are written next to the output file with a .features.toml extension, or printed to stderr"
+    )]
+    pub emit_features: bool,
+
+    #[clap(
+        long,
+        about = "Use a custom Rust type for a property, given as Namespace.Entity.Property=Type, optionally followed by ;module to (de)serialize it with. Can be repeated"
+    )]
+    pub type_override: Vec<String>,
+
+    #[clap(
+        long,
+        about = "Validate that code can be generated from the metadata document, listing any problems instead of emitting code"
+    )]
+    pub check: bool,
+
     #[clap(
         short,
         long,
         about = "Write output to file. If not specified, output will be printed to stdout"
     )]
     pub output_file: Option<PathBuf>,
-}
-
-const KEYWORDS: [&str; 1] = ["type"];
-
-fn edm_type_to_rust_type(property: &Property) -> String {
-    let inner = match property.inner {
-        PropertyType::Binary { .. } => "Vec<u8>",
-        PropertyType::Boolean { .. } => "bool",
-        PropertyType::Byte { .. } => "u8",
-        PropertyType::DateTime { .. } => "chrono::NaiveDateTime",
-        PropertyType::DateTimeOffset { .. } => "std::time::Duration",
-        PropertyType::Decimal { .. } => "f64",
-        PropertyType::Double { .. } => "f64",
-        PropertyType::Int16 { .. } => "i16",
-        PropertyType::Int32 { .. } => "i32",
-        PropertyType::String { .. } => "String",
-    };
 
-    if property.nullable {
-        format!("Option<{}>", inner)
-    } else {
-        inner.to_string()
-    }
-}
+    #[clap(
+        long,
+        about = "Generate each metadata document separately into the given directory instead of merging them, naming the output after the input file. Failures are reported without stopping the other documents"
+    )]
+    pub output_dir: Option<PathBuf>,
 
-fn entity_type_reflection(entity: &EntityType) -> String {
-    let fields: Vec<(_, _)> = entity
-        .properties
-        .iter()
-        .map(|property| {
-            let typename = format!(
-                "{} {{ nullable: {}, key: {} }}",
-                match property.inner {
-                    PropertyType::Binary { .. } => "Binary",
-                    PropertyType::Boolean { .. } => "Boolean",
-                    PropertyType::Byte { .. } => "Byte",
-                    PropertyType::DateTime { .. } => "DateTime",
-                    PropertyType::DateTimeOffset { .. } => "DateTimeOffset",
-                    PropertyType::Decimal { .. } => "Decimal",
-                    PropertyType::Double { .. } => "Double",
-                    PropertyType::Int16 { .. } => "Int16",
-                    PropertyType::Int32 { .. } => "Int32",
-                    PropertyType::String { .. } => "String",
-                },
-                property.nullable,
-                entity.key.property_ref.name == property.name
-            );
+    #[clap(
+        long,
+        about = "Stop at the first metadata document which fails to generate when using --output-dir"
+    )]
+    pub fail_fast: bool,
 
-            (property.name.clone(), typename)
-        })
-        .collect();
+    #[clap(
+        long,
+        about = "Write a standalone crate to the given directory instead, with a Cargo.toml declaring the features and dependencies the generated code needs and the code itself in src/lib.rs"
+    )]
+    pub standalone: Option<PathBuf>,
 
-    format!(
-        "&[{}]",
-        fields
-            .iter()
-            .map(|field| format!("(\"{}\", crate::OpenDataType::{})", field.0, field.1))
-            .collect::<Vec<_>>()
-            .join(", ")
-    )
+    #[clap(
+        long,
+        about = "Write the output file even if its contents are unchanged, updating its modification time"
+    )]
+    pub force: bool,
 }
 
-fn lookup_entity_type(
-    schema: &Schema,
-    navigation_property: &NavigationProperty,
-) -> Option<(String, String)> {
-    let associations = &schema.associations;
-    let namespace = format!("{}.", &schema.namespace);
-
-    for association in associations.iter() {
-        for end in &association.ends {
-            if let Some(role) = &end.role {
-                if role == &navigation_property.to_role {
-                    if let Some(entity_type) = &end.entity_type {
-                        return namespace
-                            .strip_prefix_of(entity_type)
-                            .map(String::from)
-                            .map(|name| {
-                                end.multiplicity
-                                    .as_ref()
-                                    .map(|multi| (name, multi.to_owned()))
-                            })
-                            .flatten();
-                    }
-                }
-            }
+impl Opts {
+    fn generator_options(&self) -> GeneratorOptions {
+        GeneratorOptions {
+            no_serde: self.no_serde,
+            no_empty_string_is_null: self.no_empty_string_is_null,
+            no_reflection: self.no_reflection,
+            no_expand: self.no_expand,
+            rename_all: self.rename_all,
+            empty_values_are_null: self.empty_values_are_null,
+            lenient_bools: self.lenient_bools,
+            lenient_numbers: self.lenient_numbers,
+            field_case: self.field_case.clone(),
+            any_entity: self.any_entity,
+            datetime_type: self.datetime_type.clone(),
+            decimal_type: self.decimal_type.clone(),
+            decimal_scale: self.decimal_scale,
+            key_structs: self.key_structs,
+            url_builders: self.url_builders,
+            emit_tests: self.emit_tests,
+            required_navigations: self.required_navigations,
+            nav_ptr: self.nav_ptr.clone(),
+            nav_accessors: self.nav_accessors,
+            prelude: self.prelude,
+            display: self.display,
+            typed_ids: self.typed_ids,
+            eq_ignores_navigations: self.eq_ignores_navigations,
+            fuzzing: self.fuzzing,
+            group_navigations: self.group_navigations,
+            namespace_docs: self.namespace_docs,
+            strip_prefix: self.strip_prefix.clone(),
+            triple_state_options: self.triple_state_options,
+            envelope: self.envelope,
+            entity_set_markers: self.entity_set_markers,
+            decode_collections: self.decode_collections,
+            visibility: self.visibility.clone(),
+            select_macros: self.select_macros,
+            sort_imports: self.sort_imports,
+            unsorted: self.unsorted,
+            header: self
+                .header
+                .as_ref()
+                .map(|path| std::fs::read_to_string(path).expect("failed to read header file")),
+            no_header: self.no_header,
+            emit_allow: self.emit_allow,
+            spdx: self.spdx.clone(),
+            module_prefix: self.module_prefix.clone(),
+            no_glob_reexport: self.no_glob_reexport,
+            flatten_single_schema: self.flatten_single_schema,
+            serde_mode: self.serde_mode.clone(),
+            skip_serializing_keys: self.skip_serializing_keys,
+            skip_none: self.skip_none,
+            patch_structs: self.patch_structs,
+            projections: self
+                .projection
+                .iter()
+                .map(|projection| parse_projection(projection))
+                .collect(),
+            validate: self.validate,
+            non_exhaustive: self.non_exhaustive,
+            to_json: self.to_json,
+            type_overrides: self
+                .type_override
+                .iter()
+                .map(|type_override| parse_type_override(type_override))
+                .collect(),
         }
     }
+}
+
+/// Parses a `Namespace.Entity.Property=Type[;module]` type override.
+fn parse_type_override(type_override: &str) -> (String, TypeOverride) {
+    let (property, rust_type) = type_override.split_once('=').unwrap_or_else(|| {
+        panic!(
+            "invalid type override {}, expected Namespace.Entity.Property=Type",
+            type_override
+        )
+    });
+    let (rust_type, serde_with) = match rust_type.split_once(';') {
+        Some((rust_type, serde_with)) => (rust_type, Some(serde_with.trim().to_string())),
+        None => (rust_type, None),
+    };
 
-    None
+    (
+        property.trim().to_string(),
+        TypeOverride {
+            rust_type: rust_type.trim().to_string(),
+            serde_with,
+        },
+    )
 }
 
-fn print_structure(opts: Opts) {
-    let source = std::fs::read_to_string(&opts.input_file).unwrap_or_else(|_| {
+/// Parses an `Entity:Property1,Property2` projection.
+fn parse_projection(projection: &str) -> (String, Vec<String>) {
+    let (entity, properties) = projection.split_once(':').unwrap_or_else(|| {
         panic!(
-            "failed to read input metadata file at {}",
-            opts.input_file.display()
+            "invalid projection {}, expected Entity:Property1,Property2",
+            projection
         )
     });
 
-    let project = Edmx::from_str(&source).expect("failed to parse metadata document");
-
-    let mut root = Scope::new();
-    root.raw(indoc! {"
-            // Code automatically generated using https://github.com/Datavirke/odata-rust-generator
-            // Any changes made to this file may be overwritten by future code generation runs!
-        "});
-    let mut contains_non_ascii = false;
-
-    if !opts.no_empty_string_is_null {
-        let mut function = Function::new("empty_string_as_none");
-        function.attr("cfg(feature = \"serde\")");
-        function.generic("'de").generic("D").generic("T");
-        function.arg("de", "D");
-        function.ret("Result<Option<T>, D::Error>");
-        function
-            .bound("T", "serde::Deserialize<'de>")
-            .bound("D", "serde::Deserializer<'de>");
-        function.line("let opt: Option<String> = serde::Deserialize::deserialize(de)?;");
-        function.line("let opt = opt.as_deref();");
-        function.line("match opt {");
-        function.line("\tNone | Some(\"\") => Ok(None),");
-        function.line("\tSome(s) => T::deserialize(serde::de::IntoDeserializer::into_deserializer(s)).map(Some),");
-        function.line("}");
-        root.push_fn(function);
+    (
+        entity.trim().to_string(),
+        properties
+            .split(',')
+            .map(str::trim)
+            .filter(|property| !property.is_empty())
+            .map(str::to_string)
+            .collect(),
+    )
+}
+
+#[cfg(feature = "http")]
+fn fetch_metadata(url: &str) -> String {
+    reqwest::blocking::get(url)
+        .and_then(|response| response.error_for_status())
+        .and_then(|response| response.text())
+        .unwrap_or_else(|e| panic!("failed to fetch metadata document from {}: {}", url, e))
+}
+
+#[cfg(not(feature = "http"))]
+fn fetch_metadata(url: &str) -> String {
+    panic!(
+        "failed to fetch metadata document from {}: built without the \"http\" feature",
+        url
+    )
+}
+
+/// Reads the metadata document from a file, from `stdin` if the input is `-`, or over HTTP if
+/// the input is an http(s):// URL.
+fn read_metadata<R: Read>(input_file: &Path, mut stdin: R) -> String {
+    let input = input_file.to_string_lossy();
+
+    if input == "-" {
+        let mut source = String::new();
+        stdin
+            .read_to_string(&mut source)
+            .expect("failed to read metadata document from stdin");
+        source
+    } else if input.starts_with("http://") || input.starts_with("https://") {
+        fetch_metadata(&input)
+    } else {
+        std::fs::read_to_string(input_file).unwrap_or_else(|_| {
+            panic!(
+                "failed to read input metadata file at {}",
+                input_file.display()
+            )
+        })
     }
+}
 
-    if !opts.no_reflection {
-        let mut opendata_model = Trait::new("OpenDataModel");
-        opendata_model.r#macro("#[cfg(feature = \"reflection\")]");
-        opendata_model.vis("pub");
-        opendata_model.new_fn("name").ret("&'static str");
-        opendata_model
-            .new_fn("fields")
-            .ret("&'static [(&'static str, OpenDataType)]");
-        opendata_model
-            .new_fn("relations")
-            .ret("&'static [(&'static str, &'static str)]");
-        root.push_trait(opendata_model);
-
-        let datatype = root.new_enum("OpenDataType").vis("pub");
-        datatype.r#macro("#[cfg(feature = \"reflection\")]");
-
-        datatype
-            .new_variant("Binary")
-            .named("nullable", "bool")
-            .named("key", "bool");
-        datatype
-            .new_variant("Boolean")
-            .named("nullable", "bool")
-            .named("key", "bool");
-        datatype
-            .new_variant("Byte")
-            .named("nullable", "bool")
-            .named("key", "bool");
-        datatype
-            .new_variant("DateTime")
-            .named("nullable", "bool")
-            .named("key", "bool");
-        datatype
-            .new_variant("DateTimeOffset")
-            .named("nullable", "bool")
-            .named("key", "bool");
-        datatype
-            .new_variant("Decimal")
-            .named("nullable", "bool")
-            .named("key", "bool");
-        datatype
-            .new_variant("Double")
-            .named("nullable", "bool")
-            .named("key", "bool");
-        datatype
-            .new_variant("Int16")
-            .named("nullable", "bool")
-            .named("key", "bool");
-        datatype
-            .new_variant("Int32")
-            .named("nullable", "bool")
-            .named("key", "bool");
-        datatype
-            .new_variant("String")
-            .named("nullable", "bool")
-            .named("key", "bool");
+/// Reads and parses every input file, merging them into a single metadata document.
+fn read_project(input_files: &[PathBuf]) -> Result<Edmx, String> {
+    let mut projects = Vec::new();
+    for input_file in input_files {
+        let source = read_metadata(input_file, std::io::stdin());
+        projects.push(Edmx::from_str(&source).map_err(|e| {
+            format!(
+                "failed to parse metadata document {}: {}",
+                input_file.display(),
+                e
+            )
+        })?);
     }
 
-    for schema in &project.data_services.schemas {
-        let mut path_segments: VecDeque<_> =
-            schema.namespace.split('.').map(str::to_lowercase).collect();
-        let mut head = root.get_or_new_module(&path_segments.pop_front().unwrap());
-        head.vis("pub");
+    merge_projects(projects).ok_or_else(|| "no metadata documents given".to_string())
+}
 
-        for path_segment in path_segments {
-            head = head.get_or_new_module(&path_segment);
-            head.vis("pub");
-            contains_non_ascii = contains_non_ascii || path_segment.is_ascii();
-        }
+/// Writes `contents` to `path`, skipping the write if the file is unchanged unless `force` is set.
+fn write_output(path: &Path, contents: &str, force: bool) -> std::io::Result<()> {
+    if force {
+        std::fs::write(path, contents)
+    } else {
+        write_if_changed(path, contents).map(drop)
+    }
+}
+
+/// Generates code for the metadata document, printing the warnings about it to stderr.
+fn generate(project: &Edmx, opts: &GeneratorOptions) -> String {
+    let (output, warnings) = generate_with_warnings(project, opts);
+    for warning in &warnings {
+        eprintln!("warning: {}", warning);
+    }
 
-        if !opts.no_reflection && !schema.entities.is_empty() {
-            let entity_types = head
-                .new_fn("entity_types")
-                .attr("cfg(feature = \"reflection\")")
-                .vis("pub")
-                .ret("&'static [(&'static str, &'static [(&'static str, crate::OpenDataType)])]")
-                .line("&[");
-
-            for entity in &schema.entities {
-                entity_types.line(format!(
-                    "\t(\"{}\", {}),",
-                    entity.name,
-                    entity_type_reflection(entity)
-                ));
+    output
+}
+
+/// Writes the generated code and a Cargo.toml for it into a crate at `dir`, named after the
+/// directory.
+fn write_standalone(dir: &Path, project: &Edmx, opts: &Opts) -> std::io::Result<()> {
+    let name: String = dir
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' {
+                c
+            } else {
+                '-'
             }
-            entity_types.line("]");
-        }
+        })
+        .collect();
+    let name = if name.is_empty() {
+        "odata-bindings"
+    } else {
+        &name
+    };
+
+    std::fs::create_dir_all(dir.join("src"))?;
+    let generator_options = opts.generator_options();
+    write_output(
+        &dir.join("Cargo.toml"),
+        &cargo_manifest(name, project, &generator_options),
+        opts.force,
+    )?;
+    write_output(
+        &dir.join("src").join("lib.rs"),
+        &generate(project, &generator_options),
+        opts.force,
+    )
+}
+
+/// Whether a file name matches a pattern in which `*` matches any run of characters and `?` any
+/// single character.
+fn matches_pattern(pattern: &[char], name: &[char]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some(('*', rest)) => (0..=name.len()).any(|skip| matches_pattern(rest, &name[skip..])),
+        Some(('?', rest)) => !name.is_empty() && matches_pattern(rest, &name[1..]),
+        Some((c, rest)) => name.first() == Some(c) && matches_pattern(rest, &name[1..]),
+    }
+}
+
+/// Expands directories into the .xml files they contain, and file name patterns into the files
+/// matching them, sorted by path. Other inputs are kept as they are.
+fn expand_inputs(input_files: &[PathBuf]) -> std::io::Result<Vec<PathBuf>> {
+    let mut expanded = Vec::new();
 
-        for entity in &schema.entities {
-            let obj = head.scope().new_struct(&entity.name);
-            obj.vis("pub");
-            obj.r#macro("#[derive(Debug)]");
+    for input_file in input_files {
+        let file_name = input_file
+            .file_name()
+            .map(|name| name.to_string_lossy().chars().collect::<Vec<_>>())
+            .unwrap_or_default();
 
-            if !opts.no_serde {
-                obj.r#macro("#[cfg_attr(feature = \"serde\", derive(serde::Serialize, serde::Deserialize))]");
+        let (dir, pattern) = if input_file.is_dir() {
+            (input_file.as_path(), "*.xml".chars().collect())
+        } else if file_name.contains(&'*') || file_name.contains(&'?') {
+            let dir = input_file.parent().unwrap_or_else(|| Path::new(""));
+            (dir, file_name)
+        } else {
+            expanded.push(input_file.clone());
+            continue;
+        };
+
+        // Patterns without a directory, such as *.xml, refer to the working directory.
+        let listed = if dir.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            dir
+        };
+
+        let mut matches = Vec::new();
+        for entry in std::fs::read_dir(listed)? {
+            let entry = entry?;
+            let name: Vec<_> = entry.file_name().to_string_lossy().chars().collect();
+            if entry.path().is_file() && matches_pattern(&pattern, &name) {
+                matches.push(dir.join(entry.file_name()));
             }
+        }
+        matches.sort();
+        expanded.extend(matches);
+    }
+
+    Ok(expanded)
+}
 
-            for property in &entity.properties {
-                let typename = edm_type_to_rust_type(property);
+/// Generates every input metadata document separately into `output_dir`, returning the problems
+/// encountered. Stops at the first problem if `fail_fast` is set.
+fn generate_batch(output_dir: &Path, opts: &Opts) -> Vec<String> {
+    let input_files = match expand_inputs(&opts.input_file) {
+        Ok(input_files) => input_files,
+        Err(e) => return vec![format!("failed to list input files: {}", e)],
+    };
+    if let Err(e) = std::fs::create_dir_all(output_dir) {
+        return vec![format!(
+            "failed to create output directory {}: {}",
+            output_dir.display(),
+            e
+        )];
+    }
 
-                let mut field = if KEYWORDS.contains(&property.name.as_str()) {
-                    Field::new(
-                        &format!("pub r#{}", &property.name.to_lowercase()),
-                        &typename,
+    let generator_options = opts.generator_options();
+    let mut problems = Vec::new();
+    for input_file in &input_files {
+        let result = std::fs::read_to_string(input_file)
+            .map_err(|e| format!("failed to read {}: {}", input_file.display(), e))
+            .and_then(|source| {
+                Edmx::from_str(&source).map_err(|e| {
+                    format!(
+                        "failed to parse metadata document {}: {}",
+                        input_file.display(),
+                        e
                     )
+                })
+            })
+            .and_then(|project| {
+                let problems = check_options(&project, &generator_options);
+                if problems.is_empty() {
+                    Ok(project)
                 } else {
-                    Field::new(&format!("pub {}", &property.name.to_lowercase()), &typename)
-                };
-                let mut annotations = Vec::new();
-
-                if !opts.no_empty_string_is_null && typename == "Option<String>" {
-                    annotations.push("#[cfg_attr(feature = \"serde\", serde(deserialize_with = \"crate::empty_string_as_none\"))]".to_string());
-                };
-
-                if property.name.chars().any(char::is_uppercase) {
-                    annotations.push(format!(
-                        "#[cfg_attr(feature = \"serde\", serde(rename = \"{}\"))]",
-                        property.name
-                    ));
+                    Err(format!("{}: {}", input_file.display(), problems.join(", ")))
                 }
-                field.annotation(annotations.iter().map(String::as_str).collect());
-
-                obj.push_field(field);
-            }
-
-            if !opts.no_expand {
-                for navigation_property in &entity.navigations {
-                    let (typename, multiplicity) =
-                        lookup_entity_type(schema, navigation_property).unwrap();
-
-                    let typename = match multiplicity.as_str() {
-                        "0..1" => format!("Option<Box<{}>>", typename),
-                        _ => format!("Vec<{}>", typename),
-                    };
-
-                    let mut field = if KEYWORDS.contains(&navigation_property.name.as_str()) {
-                        Field::new(
-                            &format!("pub r#{}", &navigation_property.name.to_lowercase()),
-                            &typename,
-                        )
-                    } else {
-                        Field::new(
-                            &format!("pub {}", &navigation_property.name.to_lowercase()),
-                            &typename,
-                        )
-                    };
-                    if navigation_property.name.chars().any(char::is_uppercase) {
-                        field.annotation(vec![&format!(
-                            "#[cfg_attr(feature = \"serde\", serde(rename = \"{}\", default))]",
-                            navigation_property.name
-                        )]);
-                    }
-
-                    obj.push_field(field);
+            })
+            .and_then(|project| {
+                let output_file = output_dir
+                    .join(input_file.file_stem().unwrap_or_default())
+                    .with_extension("rs");
+                let (output, warnings) = generate_with_warnings(&project, &generator_options);
+                for warning in &warnings {
+                    eprintln!("warning: {}: {}", input_file.display(), warning);
                 }
-            }
 
-            if !opts.no_reflection {
-                let fields = entity_type_reflection(entity);
-                let expansions = entity
-                    .navigations
-                    .iter()
-                    .map(|nav| {
-                        let (typename, _) = lookup_entity_type(schema, nav).unwrap();
-                        format!("(\"{}\", \"{}\")", nav.name, typename)
+                write_output(&output_file, &output, opts.force)
+                    .and_then(|_| {
+                        if opts.emit_features {
+                            write_output(
+                                &output_file.with_extension("features.toml"),
+                                &cargo_features(&project, &generator_options),
+                                opts.force,
+                            )
+                        } else {
+                            Ok(())
+                        }
                     })
-                    .collect::<Vec<_>>()
-                    .join(", ");
-
-                let opendata_model = head
-                    .new_impl(&entity.name)
-                    .impl_trait("crate::OpenDataModel");
-                opendata_model.r#macro("#[cfg(feature = \"reflection\")]");
-                opendata_model
-                    .new_fn("name")
-                    .ret("&'static str")
-                    .line(format!("\"{}\"", &entity.name));
-                opendata_model
-                    .new_fn("fields")
-                    .ret("&'static [(&'static str, crate::OpenDataType)]")
-                    .line(fields);
-
-                if !opts.no_expand {
-                    opendata_model
-                        .new_fn("relations")
-                        .ret("&'static [(&'static str, &'static str)]")
-                        .line(format!("&[{}]", expansions));
-                }
+                    .map_err(|e| format!("failed to write {}: {}", output_file.display(), e))
+            });
+
+        if let Err(problem) = result {
+            problems.push(problem);
+            if opts.fail_fast {
+                break;
             }
         }
+    }
+
+    if input_files.is_empty() {
+        problems.push("no metadata documents found".to_string());
+    }
 
-        if let Some(sets) = schema.entity_sets() {
-            for set in sets {
-                let mut path: Vec<_> = set.entity_type.split('.').map(str::to_lowercase).collect();
-                path.pop();
+    problems
+}
 
-                head.scope()
-                    .import(&format!("crate::{}", path.join("::")), &set.name)
-                    .vis("pub");
-            }
+fn print_structure(opts: Opts) {
+    if let Some(output_dir) = &opts.output_dir {
+        let problems = generate_batch(output_dir, &opts);
+        for problem in &problems {
+            eprintln!("error: {}", problem);
+        }
+
+        if !problems.is_empty() {
+            std::process::exit(1);
         }
+        return;
+    }
+
+    let project = read_project(&opts.input_file).unwrap_or_else(|e| panic!("{}", e));
+
+    let problems = check_options(&project, &opts.generator_options());
+    for problem in &problems {
+        eprintln!("error: {}", problem);
+    }
+
+    if !problems.is_empty() {
+        std::process::exit(1);
     }
 
-    if let Some(default_schema) = project.default_schema() {
-        root.import(&default_schema.namespace.to_lowercase(), "*")
-            .vis("pub");
+    if let Some(dir) = &opts.standalone {
+        write_standalone(dir, &project, &opts).expect("failed to write standalone crate");
+        return;
     }
 
-    let output = root.to_string();
+    let output = generate(&project, &opts.generator_options());
     if let Some(output_file) = &opts.output_file {
-        std::fs::write(&output_file, output).expect("failed to write output to file");
+        write_output(output_file, &output, opts.force).expect("failed to write output to file");
     } else {
         println!("{}", &output);
     }
+
+    if opts.emit_features {
+        let features = cargo_features(&project, &opts.generator_options());
+        if let Some(output_file) = &opts.output_file {
+            write_output(
+                &output_file.with_extension("features.toml"),
+                &features,
+                opts.force,
+            )
+            .expect("failed to write features to file");
+        } else {
+            eprint!("{}", features);
+        }
+    }
+}
+
+/// Runs the generator without emitting any code, exiting with a non-zero status if there are
+/// problems with the metadata document.
+fn check_structure(opts: Opts) {
+    let problems = match read_project(&opts.input_file) {
+        Ok(project) => {
+            let generator_options = opts.generator_options();
+            generate(&project, &generator_options);
+            let mut problems = check(&project);
+            problems.extend(check_options(&project, &generator_options));
+            problems
+        }
+        Err(e) => vec![e],
+    };
+
+    for problem in &problems {
+        eprintln!("error: {}", problem);
+    }
+
+    if !problems.is_empty() {
+        std::process::exit(1);
+    }
 }
 
 fn main() {
     let opts = Opts::parse();
 
-    print_structure(opts);
+    if opts.check {
+        check_structure(opts);
+    } else {
+        print_structure(opts);
+    }
 }
 
 #[cfg(test)]
@@ -403,12 +814,213 @@ mod tests {
     #[test]
     fn test_generating_code_from_xml() {
         print_structure(Opts {
-            input_file: PathBuf::from("tests/folketinget.xml"),
+            input_file: vec![PathBuf::from("tests/folketinget.xml")],
             no_serde: false,
             no_expand: false,
             no_empty_string_is_null: false,
             no_reflection: false,
             output_file: None,
+            ..Default::default()
         })
     }
+
+    #[test]
+    fn test_unchanged_output_is_not_rewritten() {
+        let output_file = std::env::temp_dir().join("odata-rust-generator-unchanged.rs");
+        let opts = || Opts {
+            input_file: vec![PathBuf::from("tests/northwind.xml")],
+            output_file: Some(output_file.clone()),
+            ..Default::default()
+        };
+        let modified = || std::fs::metadata(&output_file).unwrap().modified().unwrap();
+
+        print_structure(opts());
+        let written = modified();
+        std::thread::sleep(std::time::Duration::from_millis(50));
+
+        print_structure(opts());
+        assert_eq!(modified(), written);
+
+        print_structure(Opts {
+            force: true,
+            ..opts()
+        });
+        assert!(modified() > written);
+
+        std::fs::remove_file(&output_file).unwrap();
+    }
+
+    #[test]
+    fn test_standalone_crate() {
+        let dir = std::env::temp_dir().join("odata-rust-generator-standalone");
+        print_structure(Opts {
+            input_file: vec![PathBuf::from("tests/northwind.xml")],
+            standalone: Some(dir.clone()),
+            ..Default::default()
+        });
+
+        assert_eq!(
+            std::fs::read_to_string(dir.join("Cargo.toml")).unwrap(),
+            indoc! {r#"
+                [package]
+                name = "odata-rust-generator-standalone"
+                version = "0.1.0"
+                edition = "2018"
+
+                [features]
+                default = ["serde", "reflection"]
+                serde = ["dep:serde", "chrono/serde"]
+                reflection = []
+
+                [dependencies]
+                serde = { version = "1", features = ["derive"], optional = true }
+                chrono = "0.4"
+            "#}
+        );
+        assert!(std::fs::read_to_string(dir.join("src/lib.rs"))
+            .unwrap()
+            .contains("pub mod northwindmodel {"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_batch_generation() {
+        let output_dir = std::env::temp_dir().join("odata-rust-generator-batch");
+        let _ = std::fs::remove_dir_all(&output_dir);
+
+        for input_file in ["tests", "tests/*.xml"] {
+            let problems = generate_batch(
+                &output_dir,
+                &Opts {
+                    input_file: vec![PathBuf::from(input_file)],
+                    ..Default::default()
+                },
+            );
+            assert!(problems.is_empty(), "{:?}", problems);
+
+            let mut outputs: Vec<_> = std::fs::read_dir(&output_dir)
+                .unwrap()
+                .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+                .collect();
+            outputs.sort();
+            assert_eq!(outputs, vec!["folketinget.rs", "northwind.rs"]);
+            assert!(std::fs::read_to_string(output_dir.join("northwind.rs"))
+                .unwrap()
+                .contains("pub struct Customer {"));
+
+            std::fs::remove_dir_all(&output_dir).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_batch_generation_reports_failures() {
+        let input_dir = std::env::temp_dir().join("odata-rust-generator-batch-inputs");
+        let output_dir = std::env::temp_dir().join("odata-rust-generator-batch-outputs");
+        let _ = std::fs::remove_dir_all(&output_dir);
+        std::fs::create_dir_all(&input_dir).unwrap();
+        std::fs::write(input_dir.join("broken.xml"), "<edmx:Edmx>").unwrap();
+        std::fs::copy("tests/northwind.xml", input_dir.join("northwind.xml")).unwrap();
+
+        let opts = |fail_fast| Opts {
+            input_file: vec![input_dir.clone()],
+            fail_fast,
+            ..Default::default()
+        };
+
+        let problems = generate_batch(&output_dir, &opts(false));
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].contains("broken.xml"), "{}", problems[0]);
+        assert!(output_dir.join("northwind.rs").exists());
+        std::fs::remove_dir_all(&output_dir).unwrap();
+
+        assert_eq!(generate_batch(&output_dir, &opts(true)).len(), 1);
+        assert!(!output_dir.join("northwind.rs").exists());
+
+        std::fs::remove_dir_all(&output_dir).unwrap();
+        std::fs::remove_dir_all(&input_dir).unwrap();
+    }
+
+    #[test]
+    fn test_matches_pattern() {
+        let matches = |pattern: &str, name: &str| {
+            matches_pattern(
+                &pattern.chars().collect::<Vec<_>>(),
+                &name.chars().collect::<Vec<_>>(),
+            )
+        };
+
+        assert!(matches("*.xml", "northwind.xml"));
+        assert!(matches("north?ind.*", "northwind.xml"));
+        assert!(!matches("*.xml", "northwind.rs"));
+        assert!(!matches("?", ""));
+    }
+
+    #[test]
+    fn test_parse_type_override() {
+        let (property, type_override) =
+            parse_type_override("NorthwindModel.Customer.Phone=crate::PhoneNumber");
+        assert_eq!(property, "NorthwindModel.Customer.Phone");
+        assert_eq!(type_override.rust_type, "crate::PhoneNumber");
+        assert_eq!(type_override.serde_with, None);
+
+        let (_, type_override) = parse_type_override(
+            "NorthwindModel.Customer.Phone=crate::PhoneNumber;crate::phone_number",
+        );
+        assert_eq!(type_override.rust_type, "crate::PhoneNumber");
+        assert_eq!(
+            type_override.serde_with.as_deref(),
+            Some("crate::phone_number")
+        );
+    }
+
+    #[test]
+    fn test_parse_projection() {
+        assert_eq!(
+            parse_projection("NorthwindModel.Customer:CustomerID, CompanyName"),
+            (
+                "NorthwindModel.Customer".to_string(),
+                vec!["CustomerID".to_string(), "CompanyName".to_string()]
+            )
+        );
+    }
+
+    #[test]
+    fn test_read_metadata_from_stdin() {
+        let source = std::fs::read_to_string("tests/northwind.xml").unwrap();
+
+        assert_eq!(read_metadata(Path::new("-"), source.as_bytes()), source);
+        assert_eq!(
+            read_metadata(Path::new("tests/northwind.xml"), std::io::empty()),
+            source
+        );
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn test_read_metadata_over_http() {
+        use std::io::Write;
+        use std::net::TcpListener;
+
+        let source = std::fs::read_to_string("tests/northwind.xml").unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/$metadata", listener.local_addr().unwrap());
+
+        let body = source.clone();
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0u8; 1024];
+            let _ = stream.read(&mut request).unwrap();
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/xml\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            )
+            .unwrap();
+        });
+
+        assert_eq!(read_metadata(Path::new(&url), std::io::empty()), source);
+        server.join().unwrap();
+    }
 }